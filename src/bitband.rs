@@ -0,0 +1,48 @@
+use core::ptr;
+
+// Reference: 3.4.3 Bit-banding (ARM Cortex-M4 Processor)
+const SRAM_START: usize = 0x2000_0000;
+const SRAM_END: usize = 0x200F_FFFF;
+const SRAM_ALIAS: usize = 0x2200_0000;
+
+const PERIPHERAL_START: usize = 0x4000_0000;
+const PERIPHERAL_END: usize = 0x400F_FFFF;
+const PERIPHERAL_ALIAS: usize = 0x4200_0000;
+
+/// Returns the bit-band alias of a single bit of a register
+///
+/// Every bit of the bit-band regions is mapped to a whole word of the alias regions, so a bit can
+/// be read or written with a single word access instead of a read-modify-write
+pub fn alias<T>(register: *const T, bit: u8) -> *mut u32 {
+    if bit >= 32 {
+        panic!("Invalid bit-band bit: {}", bit);
+    }
+
+    let address = register as usize;
+    let (start, alias) = match address {
+        SRAM_START..=SRAM_END => (SRAM_START, SRAM_ALIAS),
+        PERIPHERAL_START..=PERIPHERAL_END => (PERIPHERAL_START, PERIPHERAL_ALIAS),
+        _ => panic!("Address is outside of the bit-band regions: {:#x}", address),
+    };
+
+    (alias + (address - start) * 32 + usize::from(bit) * 4) as *mut u32
+}
+
+/// Reads a single bit of a register through its bit-band alias
+///
+/// # Safety
+///
+/// `register` must point to a register or memory that is valid to read
+pub unsafe fn read<T>(register: *const T, bit: u8) -> bool {
+    ptr::read_volatile(alias(register, bit)) != 0
+}
+
+/// Writes a single bit of a register through its bit-band alias
+///
+/// # Safety
+///
+/// `register` must point to a register or memory that is valid to write, and the bit must not be
+/// owned by anyone else
+pub unsafe fn write<T>(register: *const T, bit: u8, value: bool) {
+    ptr::write_volatile(alias(register, bit), u32::from(value));
+}
@@ -0,0 +1,391 @@
+use core::sync::atomic::{self, Ordering};
+
+use mk20d7::{self, dma, DMA, DMAMUX};
+use mk20d7::sim::{SCGC6, SCGC7};
+
+use sealed::Sealed;
+
+/// Extension trait to split the DMA controller and multiplexer in independent channels
+pub trait DmaExt {
+    /// The channels to split the DMA into
+    type Channels;
+
+    /// Splits the DMA controller and multiplexer into independent channels
+    fn split(self, scgc6: &SCGC6, scgc7: &SCGC7) -> Self::Channels;
+}
+
+/// Peripheral request that can be routed to a DMA channel
+pub trait DmaSource: Sealed {
+    /// DMA multiplexer request source slot
+    // Reference: 3.3.9.1 DMA request multiplexer channel sources
+    const SOURCE: u8;
+
+    /// Stops raising DMA requests, called once a transfer completes
+    fn disable_dma(&mut self) {}
+}
+
+/// Size of a single read or write of a DMA transfer
+pub enum TransferSize {
+    /// 8-bit transfer
+    Byte,
+
+    /// 16-bit transfer
+    HalfWord,
+
+    /// 32-bit transfer
+    Word,
+}
+
+/// DMA channel interrupt event
+pub enum Event {
+    /// Major loop count is half complete
+    HalfComplete,
+
+    /// Major loop count is complete
+    Complete,
+}
+
+// Transfer Control Descriptor, the layout is the same for every channel
+// Reference: 21.3.17 TCD Source Address (DMA_TCDn_SADDR)
+#[repr(C)]
+struct Tcd {
+    saddr: dma::TCD_SADDR,
+    soff: dma::TCD_SOFF,
+    attr: dma::TCD_ATTR,
+    nbytes: dma::TCD_NBYTES_MLNO,
+    slast: dma::TCD_SLAST,
+    daddr: dma::TCD_DADDR,
+    doff: dma::TCD_DOFF,
+    citer: dma::TCD_CITER_ELINKNO,
+    dlastsga: dma::TCD_DLASTSGA,
+    csr: dma::TCD_CSR,
+    biter: dma::TCD_BITER_ELINKNO,
+}
+
+fn dma<'a>() -> &'a mk20d7::dma::RegisterBlock {
+    unsafe { &(*DMA::ptr()) }
+}
+
+fn dmamux<'a>() -> &'a mk20d7::dmamux::RegisterBlock {
+    unsafe { &(*DMAMUX::ptr()) }
+}
+
+fn tcd<'a>(channel: u8) -> &'a Tcd {
+    unsafe { &*(&dma().tcd0_saddr as *const _ as *const Tcd).offset(isize::from(channel)) }
+}
+
+fn transfer_size(size: TransferSize) -> u16 {
+    match size {
+        TransferSize::Byte => 0b000,
+        TransferSize::HalfWord => 0b001,
+        TransferSize::Word => 0b010,
+    }
+}
+
+// Reference: 21.3.28 TCD Control and Status (DMA_TCDn_CSR)
+const CSR_INTMAJOR: u16 = 1 << 1;
+const CSR_INTHALF: u16 = 1 << 2;
+const CSR_DREQ: u16 = 1 << 3;
+const CSR_ACTIVE: u16 = 1 << 6;
+const CSR_DONE: u16 = 1 << 7;
+
+// Reference: 21.3.23 TCD Current Minor Loop Link, Major Loop Count (DMA_TCDn_CITER_ELINKNO)
+const MAJOR_LOOP_COUNT_MAX: u16 = 0x7FFF;
+
+/// DMA channel
+///
+/// The channel owns its Transfer Control Descriptor and its DMA multiplexer slot
+pub trait Channel: Sealed {
+    /// Channel number
+    const NUMBER: u8;
+
+    /// Routes a peripheral request to this channel
+    // Reference: 20.3.1 Channel Configuration register (DMAMUX_CHCFGn)
+    fn bind<S: DmaSource>(&mut self, _source: &S) {
+        let chcfg = &dmamux().chcfg[usize::from(Self::NUMBER)];
+
+        // The channel must be disabled before its source is changed
+        chcfg.write(|w| w.enbl().clear_bit());
+        chcfg.write(|w| unsafe { w.source().bits(S::SOURCE).enbl().set_bit() });
+    }
+
+    /// Disconnects this channel from any peripheral request
+    fn unbind(&mut self) {
+        dmamux().chcfg[usize::from(Self::NUMBER)].write(|w| w.enbl().clear_bit());
+    }
+
+    /// Sets the address and the signed offset applied after every read
+    ///
+    /// # Safety
+    ///
+    /// The DMA controller will read from `address` regardless of who owns that memory
+    unsafe fn set_source(&mut self, address: u32, offset: i16) {
+        let tcd = tcd(Self::NUMBER);
+        tcd.saddr.write(|w| w.bits(address));
+        tcd.soff.write(|w| w.bits(offset as u16));
+    }
+
+    /// Sets the adjustment applied to the source address when the major loop completes
+    ///
+    /// # Safety
+    ///
+    /// The adjusted source address is read by the next major loop, it must stay within memory
+    /// that may be read
+    unsafe fn set_source_last_adjustment(&mut self, adjustment: i32) {
+        tcd(Self::NUMBER).slast.write(|w| unsafe { w.bits(adjustment as u32) });
+    }
+
+    /// Sets the address and the signed offset applied after every write
+    ///
+    /// # Safety
+    ///
+    /// The DMA controller will write to `address` regardless of who owns that memory
+    unsafe fn set_destination(&mut self, address: u32, offset: i16) {
+        let tcd = tcd(Self::NUMBER);
+        tcd.daddr.write(|w| w.bits(address));
+        tcd.doff.write(|w| w.bits(offset as u16));
+    }
+
+    /// Sets the adjustment applied to the destination address when the major loop completes
+    ///
+    /// # Safety
+    ///
+    /// The adjusted destination address is written by the next major loop, it must stay within
+    /// memory that may be written
+    unsafe fn set_destination_last_adjustment(&mut self, adjustment: i32) {
+        tcd(Self::NUMBER).dlastsga.write(|w| unsafe { w.bits(adjustment as u32) });
+    }
+
+    /// Sets the size of every read from the source and every write to the destination
+    ///
+    /// # Safety
+    ///
+    /// Larger transfers move further from the source and destination addresses, the whole
+    /// transfer must stay within their memory
+    unsafe fn set_transfer_size(&mut self, source: TransferSize, destination: TransferSize) {
+        // Reference: 21.3.19 TCD Transfer Attributes (DMA_TCDn_ATTR)
+        let bits = (transfer_size(source) << 8) | transfer_size(destination);
+        tcd(Self::NUMBER).attr.write(|w| unsafe { w.bits(bits) });
+    }
+
+    /// Sets the number of bytes transferred for every request
+    ///
+    /// # Safety
+    ///
+    /// The bytes of every major loop must fit the memory at the source and destination addresses
+    unsafe fn set_minor_loop_bytes(&mut self, bytes: u32) {
+        tcd(Self::NUMBER).nbytes.write(|w| unsafe { w.bits(bytes) });
+    }
+
+    /// Sets the number of requests serviced before the major loop completes
+    ///
+    /// # Safety
+    ///
+    /// The bytes of every major loop must fit the memory at the source and destination addresses
+    unsafe fn set_major_loop_count(&mut self, count: u16) {
+        if count == 0 || count > MAJOR_LOOP_COUNT_MAX {
+            panic!("Invalid DMA major loop count: {}", count);
+        }
+
+        let tcd = tcd(Self::NUMBER);
+        tcd.citer.write(|w| unsafe { w.bits(count) });
+        tcd.biter.write(|w| unsafe { w.bits(count) });
+    }
+
+    /// Returns the number of requests left before the major loop completes
+    fn major_loop_count_remaining(&self) -> u16 {
+        tcd(Self::NUMBER).citer.read().bits() & MAJOR_LOOP_COUNT_MAX
+    }
+
+    /// Stops accepting requests once the major loop completes
+    fn set_disable_request_on_completion(&mut self, disable: bool) {
+        tcd(Self::NUMBER).csr.modify(|r, w| unsafe {
+            if disable {
+                w.bits(r.bits() | CSR_DREQ)
+            } else {
+                w.bits(r.bits() & !CSR_DREQ)
+            }
+        });
+    }
+
+    /// Starts listening for an interrupt event
+    fn listen(&mut self, event: Event) {
+        let bit = match event {
+            Event::HalfComplete => CSR_INTHALF,
+            Event::Complete => CSR_INTMAJOR,
+        };
+        tcd(Self::NUMBER).csr.modify(|r, w| unsafe { w.bits(r.bits() | bit) });
+    }
+
+    /// Stops listening for an interrupt event
+    fn unlisten(&mut self, event: Event) {
+        let bit = match event {
+            Event::HalfComplete => CSR_INTHALF,
+            Event::Complete => CSR_INTMAJOR,
+        };
+        tcd(Self::NUMBER).csr.modify(|r, w| unsafe { w.bits(r.bits() & !bit) });
+    }
+
+    /// Clears the interrupt request of this channel
+    fn clear_interrupt(&mut self) {
+        dma().cint.write(|w| unsafe { w.cint().bits(Self::NUMBER) });
+    }
+
+    /// Starts accepting requests from the bound peripheral
+    ///
+    /// # Safety
+    ///
+    /// The channel must be configured to only access memory it may access until the requests
+    /// are disabled
+    unsafe fn enable_request(&mut self) {
+        dma().serq.write(|w| unsafe { w.serq().bits(Self::NUMBER) });
+    }
+
+    /// Stops accepting requests from the bound peripheral
+    fn disable_request(&mut self) {
+        dma().cerq.write(|w| unsafe { w.cerq().bits(Self::NUMBER) });
+    }
+
+    /// Starts a transfer from software
+    ///
+    /// # Safety
+    ///
+    /// The channel must be configured to only access memory it may access until the transfer
+    /// completes
+    unsafe fn start(&mut self) {
+        dma().ssrt.write(|w| unsafe { w.ssrt().bits(Self::NUMBER) });
+    }
+
+    /// Returns `true` while the channel is executing a transfer
+    fn is_active(&self) -> bool {
+        tcd(Self::NUMBER).csr.read().bits() & CSR_ACTIVE != 0
+    }
+
+    /// Returns `true` once the major loop has completed
+    fn is_done(&self) -> bool {
+        tcd(Self::NUMBER).csr.read().bits() & CSR_DONE != 0
+    }
+
+    /// Clears the done flag of this channel
+    fn clear_done(&mut self) {
+        dma().cdne.write(|w| unsafe { w.cdne().bits(Self::NUMBER) });
+    }
+
+    /// Returns `true` if the last transfer of this channel caused an error
+    fn has_error(&self) -> bool {
+        dma().err.read().bits() & (1 << Self::NUMBER) != 0
+    }
+
+    /// Clears the error flag of this channel
+    fn clear_error(&mut self) {
+        dma().cerr.write(|w| unsafe { w.cerr().bits(Self::NUMBER) });
+    }
+}
+
+/// DMA transfer in progress, owns the channel, the buffer and the peripheral until it completes
+pub struct Transfer<CHANNEL, BUFFER, PERIPHERAL> {
+    channel: CHANNEL,
+    buffer: BUFFER,
+    peripheral: PERIPHERAL,
+}
+
+impl<CHANNEL, BUFFER, PERIPHERAL> Transfer<CHANNEL, BUFFER, PERIPHERAL>
+where
+    CHANNEL: Channel,
+    PERIPHERAL: DmaSource,
+{
+    // The channel must be fully configured to only access the buffer, the buffer accesses are not
+    // reordered after this
+    pub(crate) unsafe fn start(mut channel: CHANNEL, buffer: BUFFER, peripheral: PERIPHERAL) -> Self {
+        channel.clear_done();
+        atomic::compiler_fence(Ordering::Release);
+        channel.enable_request();
+        Transfer { channel, buffer, peripheral }
+    }
+
+    /// Returns `true` once the transfer has completed
+    pub fn is_done(&self) -> bool {
+        self.channel.is_done()
+    }
+
+    /// Returns the number of requests left before the transfer completes
+    pub fn remaining(&self) -> u16 {
+        self.channel.major_loop_count_remaining()
+    }
+
+    /// Waits for the transfer to complete, and releases the channel, the buffer and the peripheral
+    pub fn wait(mut self) -> (CHANNEL, BUFFER, PERIPHERAL) {
+        while !self.channel.is_done() {}
+
+        self.peripheral.disable_dma();
+        self.channel.disable_request();
+        self.channel.clear_done();
+        atomic::compiler_fence(Ordering::Acquire);
+
+        (self.channel, self.buffer, self.peripheral)
+    }
+}
+
+macro_rules! dma {
+    ([ $($CX:ident: ($chx:ident, $x:expr, $docchannel:expr),)+ ]) => {
+        /// DMA channels
+        pub struct Channels {
+            $(
+                #[doc = "DMA Channel "]
+                #[doc = $docchannel]
+                pub $chx: $CX,
+            )+
+        }
+
+        impl DmaExt for (DMA, DMAMUX) {
+            type Channels = Channels;
+
+            fn split(self, scgc6: &SCGC6, scgc7: &SCGC7) -> Self::Channels {
+                // Enable the DMA multiplexer and the DMA controller modules
+                // Reference: 12.2.13 System Clock Gating Control Register 6 (SIM_SCGC6)
+                // Reference: 12.2.14 System Clock Gating Control Register 7 (SIM_SCGC7)
+                scgc6.modify(|_, w| w.dmamux().set_bit());
+                scgc7.modify(|_, w| w.dma().set_bit());
+
+                Channels {
+                    $(
+                        $chx: $CX { _0: () },
+                    )+
+                }
+            }
+        }
+
+        $(
+            #[doc = "DMA Channel "]
+            #[doc = $docchannel]
+            pub struct $CX {
+                _0: (),
+            }
+
+            impl Sealed for $CX {}
+
+            impl Channel for $CX {
+                const NUMBER: u8 = $x;
+            }
+        )+
+    }
+}
+
+dma!([
+    C0: (ch0, 0, "0"),
+    C1: (ch1, 1, "1"),
+    C2: (ch2, 2, "2"),
+    C3: (ch3, 3, "3"),
+    C4: (ch4, 4, "4"),
+    C5: (ch5, 5, "5"),
+    C6: (ch6, 6, "6"),
+    C7: (ch7, 7, "7"),
+    C8: (ch8, 8, "8"),
+    C9: (ch9, 9, "9"),
+    C10: (ch10, 10, "10"),
+    C11: (ch11, 11, "11"),
+    C12: (ch12, 12, "12"),
+    C13: (ch13, 13, "13"),
+    C14: (ch14, 14, "14"),
+    C15: (ch15, 15, "15"),
+]);
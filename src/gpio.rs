@@ -1,5 +1,12 @@
 use core::marker::PhantomData;
-use mk20d7::sim::SCGC5;
+
+use hal::digital::{
+    OutputPin, StatefulOutputPin, InputPin,
+    ToggleableOutputPin,
+};
+use mk20d7::{self, sim::SCGC5, PTA, PTB, PTC, PTD, PTE};
+
+use sealed::Sealed;
 
 /// Extension trait to split a GPIO peripheral in independent pins and registers
 pub trait GpioExt {
@@ -10,6 +17,16 @@ pub trait GpioExt {
     fn split(self, scgc5: &SCGC5) -> Self::Parts;
 }
 
+/// Pin that can be returned to its disabled state, used by drivers that release their pins
+pub trait Disable: Sealed {
+    /// The pin in its disabled state
+    type Disabled;
+
+    /// Returns the pin to alternate function 0 (analog or disabled) with the pull resistors and
+    /// requests off, which minimizes its leakage
+    fn disable(self) -> Self::Disabled;
+}
+
 /// Input mode (type state)
 pub struct Input<MODE> {
     _mode: PhantomData<MODE>,
@@ -35,6 +52,16 @@ pub struct PushPull;
 /// Open drain output (type state)
 pub struct OpenDrain;
 
+/// Dynamic mode (type state), the pin is switched between input and output at runtime
+pub struct Dynamic;
+
+/// Error raised when a dynamic pin is used in a mode that it is not in
+#[derive(Debug)]
+pub enum PinModeError {
+    /// The pin is not in the mode required by the operation
+    IncorrectMode,
+}
+
 pub struct Alternate<MODE> {
     _mode: PhantomData<MODE>,
 }
@@ -63,28 +90,338 @@ pub struct ALT6;
 /// Alternate function 7 (type state, chip specific / JTAG / NMI)
 pub struct ALT7;
 
-// Pin mux controller mode
-enum PinMux {
+/// Pin mux controller mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinMux {
+    /// Alternate function 0 (Analog)
     ALT0,
+
+    /// Alternate function 1 (GPIO)
     ALT1,
+
+    /// Alternate function 2 (chip specific)
     ALT2,
+
+    /// Alternate function 3 (chip specific)
     ALT3,
+
+    /// Alternate function 4 (chip specific)
     ALT4,
+
+    /// Alternate function 5 (chip specific)
     ALT5,
+
+    /// Alternate function 6 (chip specific)
     ALT6,
+
+    /// Alternate function 7 (chip specific / JTAG / NMI)
     ALT7,
 }
 
 // Pin mode (when pin is in ALT1 gpio mode)
 enum PinMode {
     Output,
-
-    #[allow(dead_code)]
     Input,
 }
 
+/// Pin pull resistor (when pin is a digital input)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinPull {
+    /// No pull resistor
+    None,
+
+    /// Pull up resistor
+    Up,
+
+    /// Pull down resistor
+    Down,
+}
+
+/// Pin Control Register configuration written to many pins at once through the GPCLR and GPCHR
+/// registers
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PinConfig {
+    /// Pin mux controller mode
+    pub mux: PinMux,
+
+    /// Pull resistor
+    pub pull: PinPull,
+
+    /// High drive strength, when the pin is a digital output
+    pub high_drive_strength: bool,
+
+    /// Slow slew rate, when the pin is a digital output
+    pub slow_slew_rate: bool,
+
+    /// Open drain, when the pin is a digital output
+    pub open_drain: bool,
+
+    /// Passive input filter, when the pin is a digital input
+    pub passive_filter: bool,
+}
+
+impl Default for PinConfig {
+    /// Analog or disabled pin without pull resistor, the reset state of most pins
+    fn default() -> Self {
+        PinConfig {
+            mux: PinMux::ALT0,
+            pull: PinPull::None,
+            high_drive_strength: false,
+            slow_slew_rate: false,
+            open_drain: false,
+            passive_filter: false,
+        }
+    }
+}
+
+impl PinConfig {
+    // Lower half of the Pin Control Register, the only half the global pin control registers write
+    // Reference: 11.14.1 Pin Control Register n (PORTx_PCRn)
+    fn bits(&self) -> u16 {
+        let mux = match self.mux {
+            PinMux::ALT0 => 0b000,
+            PinMux::ALT1 => 0b001,
+            PinMux::ALT2 => 0b010,
+            PinMux::ALT3 => 0b011,
+            PinMux::ALT4 => 0b100,
+            PinMux::ALT5 => 0b101,
+            PinMux::ALT6 => 0b110,
+            PinMux::ALT7 => 0b111,
+        };
+        let pull = match self.pull {
+            PinPull::None => 0b00,
+            PinPull::Up => 0b11,
+            PinPull::Down => 0b10,
+        };
+
+        (mux << 8)
+            | (u16::from(self.high_drive_strength) << 6)
+            | (u16::from(self.open_drain) << 5)
+            | (u16::from(self.passive_filter) << 4)
+            | (u16::from(self.slow_slew_rate) << 2)
+            | pull
+    }
+}
+
+/// Pin edge that triggers a request
+pub enum Edge {
+    /// Rising edge
+    Rising,
+
+    /// Falling edge
+    Falling,
+
+    /// Rising or falling edge
+    Either,
+}
+
+/// General Purpose Input/Output Port
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Port {
+    /// Port A
+    A,
+
+    /// Port B
+    B,
+
+    /// Port C
+    C,
+
+    /// Port D
+    D,
+
+    /// Port E
+    E,
+}
+
+impl Port {
+    // Every port has the same General Purpose Input/Output register layout
+    // Reference: 49.2 Memory map and register definition
+    fn gpio<'a>(self) -> &'a mk20d7::pta::RegisterBlock {
+        let ptr = match self {
+            Port::A => PTA::ptr(),
+            Port::B => PTB::ptr() as *const _,
+            Port::C => PTC::ptr() as *const _,
+            Port::D => PTD::ptr() as *const _,
+            Port::E => PTE::ptr() as *const _,
+        };
+        unsafe { &*ptr }
+    }
+}
+
+/// Fully erased pin, both the port and the pin number are known only at runtime
+pub struct Pin<MODE> {
+    port: Port,
+    i: u8,
+    _mode: PhantomData<MODE>,
+}
+
+impl<MODE> Pin<MODE> {
+    /// Returns the port of this pin
+    pub fn port(&self) -> Port {
+        self.port
+    }
+
+    /// Returns the pin number within its port
+    pub fn pin(&self) -> u8 {
+        self.i
+    }
+}
+
+impl<MODE> StatefulOutputPin for Pin<Output<MODE>> {
+    fn is_set_high(&self) -> bool {
+        !self.is_set_low()
+    }
+
+    fn is_set_low(&self) -> bool {
+        self.port.gpio().pdor.read().bits() & (1 << self.i) == 0
+    }
+}
+
+impl<MODE> OutputPin for Pin<Output<MODE>> {
+    fn set_high(&mut self) {
+        self.port.gpio().psor.write(|w| unsafe { w.bits(1 << self.i) })
+    }
+
+    fn set_low(&mut self) {
+        self.port.gpio().pcor.write(|w| unsafe { w.bits(1 << self.i) })
+    }
+}
+
+impl<MODE> ToggleableOutputPin for Pin<Output<MODE>> {
+    fn toggle(&mut self) {
+        self.port.gpio().ptor.write(|w| unsafe { w.bits(1 << self.i) })
+    }
+}
+
+impl<MODE> InputPin for Pin<Input<MODE>> {
+    fn is_high(&self) -> bool {
+        !self.is_low()
+    }
+
+    fn is_low(&self) -> bool {
+        self.port.gpio().pdir.read().bits() & (1 << self.i) == 0
+    }
+}
+
+/// Input or output pins of a single port that can be read together as a parallel bus
+pub trait BusPins: Sealed {
+    /// General Purpose Input/Output port the pins belong to
+    type Gpio;
+
+    /// Returns the port of the pins
+    fn port(&self) -> Port;
+
+    /// Returns the mask of the pins within their port
+    fn mask(&self) -> u32;
+}
+
+/// Output pins of a single port that can be driven together as a parallel bus
+pub trait OutputBusPins: BusPins {}
+
+macro_rules! bus_pins {
+    ($( ($($T:ident: $idx:tt),+), )+) => {
+        $(
+            impl<A, $($T),+> Sealed for (A, $($T),+) {}
+
+            impl<A: BusPins, $($T),+> BusPins for (A, $($T),+)
+            where
+                $($T: BusPins<Gpio = A::Gpio>),+
+            {
+                type Gpio = A::Gpio;
+
+                fn port(&self) -> Port {
+                    self.0.port()
+                }
+
+                fn mask(&self) -> u32 {
+                    self.0.mask() $(| self.$idx.mask())+
+                }
+            }
+
+            impl<A: OutputBusPins, $($T),+> OutputBusPins for (A, $($T),+)
+            where
+                $($T: OutputBusPins<Gpio = A::Gpio>),+
+            {}
+        )+
+    }
+}
+
+bus_pins! {
+    (B: 1),
+    (B: 1, C: 2),
+    (B: 1, C: 2, D: 3),
+    (B: 1, C: 2, D: 3, E: 4),
+    (B: 1, C: 2, D: 3, E: 4, F: 5),
+    (B: 1, C: 2, D: 3, E: 4, F: 5, G: 6),
+    (B: 1, C: 2, D: 3, E: 4, F: 5, G: 6, H: 7),
+}
+
+/// Parallel bus made of pins of a single port
+///
+/// All the pins of the bus are read with a single register access, bits of the words are in the
+/// same positions as the pins within the port. A bus of output pins can also be driven
+pub struct PortBus<PINS> {
+    pins: PINS,
+    port: Port,
+    mask: u32,
+}
+
+impl<PINS: BusPins> PortBus<PINS> {
+    /// Takes ownership of the pins of the bus
+    pub fn new(pins: PINS) -> Self {
+        let port = pins.port();
+        let mask = pins.mask();
+        PortBus { pins, port, mask }
+    }
+
+    /// Releases the pins of the bus
+    pub fn free(self) -> PINS {
+        self.pins
+    }
+
+    /// Returns the mask of the pins of the bus within their port
+    pub fn mask(&self) -> u32 {
+        self.mask
+    }
+
+    /// Reads every pin of the bus at once, bits of pins outside of the bus are zero
+    pub fn read(&self) -> u32 {
+        self.port.gpio().pdir.read().bits() & self.mask
+    }
+}
+
+impl<PINS: OutputBusPins> PortBus<PINS> {
+    /// Drives every pin of the bus, pins outside of the bus are left untouched
+    ///
+    /// The pins driven high are set with one register write, then the pins driven low are cleared
+    /// with a second one, so the bus briefly holds the high bits of both words in between
+    // Reference: 49.2.2 Port Set Output Register (GPIOx_PSOR)
+    // Reference: 49.2.3 Port Clear Output Register (GPIOx_PCOR)
+    pub fn write(&mut self, word: u32) {
+        let gpio = self.port.gpio();
+        gpio.psor.write(|w| unsafe { w.bits(word & self.mask) });
+        gpio.pcor.write(|w| unsafe { w.bits(!word & self.mask) });
+    }
+
+    /// Drives high the pins of the bus that are set in `word`
+    pub fn set(&mut self, word: u32) {
+        self.port.gpio().psor.write(|w| unsafe { w.bits(word & self.mask) });
+    }
+
+    /// Drives low the pins of the bus that are set in `word`
+    pub fn clear(&mut self, word: u32) {
+        self.port.gpio().pcor.write(|w| unsafe { w.bits(word & self.mask) });
+    }
+
+    /// Inverts the pins of the bus that are set in `word`
+    pub fn toggle(&mut self, word: u32) {
+        self.port.gpio().ptor.write(|w| unsafe { w.bits(word & self.mask) });
+    }
+}
+
 macro_rules! gpio {
-    ($PORTX:ident, $portx:ident, $PTX:ident, $ptx:ident, $gpiox:ident, $docport:expr, [ $($PTXi:ident: ($ptxi:ident, $i:expr, $MODE:ty, $docpin:expr),)+]) =>
+    ($PORTX:ident, $portx:ident, $PTX:ident, $ptx:ident, $gpiox:ident, $PTXx:ident, $Port:ident, $docport:expr, $dmasource:expr, [ $($(#[$attr:meta])* $PTXi:ident: ($ptxi:ident, $i:expr, $MODE:ty, $docpin:expr),)+]) =>
     {
         #[doc = "General Purpose Input/Output Port "]
         #[doc = $docport]
@@ -93,16 +430,21 @@ macro_rules! gpio {
 
             use hal::digital::{
                 OutputPin, StatefulOutputPin, InputPin,
-                toggleable,
+                ToggleableOutputPin,
             };
 
             use mk20d7::{sim::SCGC5, $PORTX, $PTX, $portx, $ptx};
 
+            #[cfg(feature = "bitband")]
+            use bitband;
+            use dma::DmaSource;
+            use sealed::Sealed;
+
             use super::{
-                Floating, GpioExt, Input, Output,
-                PushPull,
+                BusPins, Disable, Dynamic, OutputBusPins, Floating, GpioExt, Input, OpenDrain, Output, Pin,
+                PinConfig, PinModeError, Port, PullDown, PullUp, PushPull,
                 Alternate, ALT0, ALT1, ALT2, ALT3, ALT4, ALT5, ALT6, ALT7,
-                PinMux, PinMode,
+                Edge, PinMux, PinMode, PinPull,
             };
 
             /// General Purpose Input/Output and Pin Control and Interrupts parts
@@ -153,6 +495,7 @@ macro_rules! gpio {
                     #[doc = $docport]
                     #[doc = " Pin "]
                     #[doc = $docpin]
+                    $(#[$attr])*
                     pub $ptxi: $PTXi<$MODE>,
                 )+
             }
@@ -163,7 +506,8 @@ macro_rules! gpio {
                 fn split(self, scgc5: &SCGC5) -> Self::Parts {
                     // Enable the GPIO module
                     // Reference: 10.2.3 Clock gating
-                    scgc5.write(|w| w.$portx().set_bit());
+                    // Reference: 12.2.12 System Clock Gating Control Register 5 (SIM_SCGC5)
+                    scgc5.modify(|_, w| w.$portx().set_bit());
 
                     Parts {
                         dfcr: DFCR { _0: () },
@@ -180,6 +524,7 @@ macro_rules! gpio {
                         psor: PSOR { _0: () },
                         ptor: PTOR { _0: () },
                         $(
+                            $(#[$attr])*
                             $ptxi: $PTXi {_mode: PhantomData},
                         )+
                     }
@@ -227,11 +572,57 @@ macro_rules! gpio {
                 _0: (),
             }
 
+            impl GPCHR {
+                pub(crate) fn gpchr(&mut self) -> &$portx::GPCHR {
+                    unsafe { &(*$PORTX::ptr()).gpchr }
+                }
+
+                /// Writes `config` to the Pin Control Register of every pin 16 to 31 that is set in
+                /// `mask`, with a single register write
+                ///
+                /// # Safety
+                ///
+                /// The type state of the pins is not changed, so the pins in `mask` must not be
+                /// owned by anyone else
+                // Reference: 11.14.3 Global Pin Control High Register (PORTx_GPCHR)
+                pub unsafe fn configure(&mut self, mask: u32, config: &PinConfig) {
+                    if mask & 0x0000_FFFF != 0 {
+                        panic!("Invalid global pin control high mask: {:#x}", mask);
+                    }
+
+                    let bits = config.bits();
+                    self.gpchr().write(|w| unsafe { w.gpwe().bits((mask >> 16) as u16).gpwd().bits(bits) });
+                }
+            }
+
             /// Global Pin Control Low Register
             pub struct GPCLR {
                 _0: (),
             }
 
+            impl GPCLR {
+                pub(crate) fn gpclr(&mut self) -> &$portx::GPCLR {
+                    unsafe { &(*$PORTX::ptr()).gpclr }
+                }
+
+                /// Writes `config` to the Pin Control Register of every pin 0 to 15 that is set in
+                /// `mask`, with a single register write
+                ///
+                /// # Safety
+                ///
+                /// The type state of the pins is not changed, so the pins in `mask` must not be
+                /// owned by anyone else
+                // Reference: 11.14.2 Global Pin Control Low Register (PORTx_GPCLR)
+                pub unsafe fn configure(&mut self, mask: u32, config: &PinConfig) {
+                    if mask & 0xFFFF_0000 != 0 {
+                        panic!("Invalid global pin control low mask: {:#x}", mask);
+                    }
+
+                    let bits = config.bits();
+                    self.gpclr().write(|w| unsafe { w.gpwe().bits(mask as u16).gpwd().bits(bits) });
+                }
+            }
+
             /// Interrupt Status Flag Register
             pub struct ISFR {
                 _0: (),
@@ -316,10 +707,32 @@ macro_rules! gpio {
             }
 
             impl PTOR {
-                #[allow(dead_code)]
                 pub(crate) fn ptor(&mut self) -> &$ptx::PTOR {
                     unsafe { &(*$PTX::ptr()).ptor }
                 }
+
+                /// Inverts the output of every pin of this port that is set in `mask`, with a
+                /// single register write
+                ///
+                /// # Safety
+                ///
+                /// The type state of the pins is not changed, so the pins in `mask` must not be
+                /// owned by anyone else
+                // Reference: 49.2.4 Port Toggle Output Register (GPIOx_PTOR)
+                pub unsafe fn toggle(&mut self, mask: u32) {
+                    self.ptor().write(|w| unsafe { w.bits(mask) })
+                }
+            }
+
+            /// DMA request raised by pin edges on this port
+            pub struct DmaRequest {
+                _0: (),
+            }
+
+            impl Sealed for DmaRequest {}
+
+            impl DmaSource for DmaRequest {
+                const SOURCE: u8 = $dmasource;
             }
 
             fn set_pin_mux(pin: usize, pcr: &mut PCR, pin_mux: PinMux) {
@@ -337,18 +750,129 @@ macro_rules! gpio {
                 pcr.pcr()[pin].write(|w| w.mux().variant(alt));
             }
 
+            #[cfg(not(feature = "bitband"))]
             fn set_pin_mode(pin: u32, pddr: &mut PDDR, pin_mode: PinMode) {
                 let bit = match pin_mode {
                     PinMode::Output => 1 << pin,
-                    PinMode::Input => !(1 << pin),
+                    PinMode::Input => 0,
                 };
-                pddr.pddr().modify(|r, w| unsafe { w.bits(r.bits() | bit) });
+                pddr.pddr().modify(|r, w| unsafe { w.bits((r.bits() & !(1 << pin)) | bit) });
+            }
+
+            #[cfg(feature = "bitband")]
+            fn set_pin_mode(pin: u32, pddr: &mut PDDR, pin_mode: PinMode) {
+                let output = match pin_mode {
+                    PinMode::Output => true,
+                    PinMode::Input => false,
+                };
+                unsafe { bitband::write(pddr.pddr(), pin as u8, output) }
+            }
+
+            fn set_pin_pull(pin: usize, pcr: &mut PCR, pin_pull: PinPull) {
+                pcr.pcr()[pin].modify(|_, w| match pin_pull {
+                    PinPull::None => w.pe().clear_bit(),
+                    PinPull::Up => w.pe().set_bit().ps().set_bit(),
+                    PinPull::Down => w.pe().set_bit().ps().clear_bit(),
+                });
+            }
+
+            fn set_pin_request(pin: usize, pcr: &mut PCR, irqc: $portx::pcr::IRQCW) {
+                pcr.pcr()[pin].modify(|_, w| w.irqc().variant(irqc));
+            }
+
+            fn set_pin_open_drain(pin: usize, pcr: &mut PCR, open_drain: bool) {
+                pcr.pcr()[pin].modify(|_, w| w.ode().bit(open_drain));
+            }
+
+            #[doc = "General Purpose Input/Output Port "]
+            #[doc = $docport]
+            #[doc = " partially erased pin, the pin number is known only at runtime"]
+            pub struct $PTXx<MODE> {
+                i: u8,
+                _mode: PhantomData<MODE>,
+            }
+
+            impl<MODE> $PTXx<MODE> {
+                /// Returns the pin number within this port
+                pub fn pin(&self) -> u8 {
+                    self.i
+                }
+
+                /// Erases the port from the type
+                pub fn downgrade(self) -> Pin<MODE> {
+                    Pin { port: Port::$Port, i: self.i, _mode: PhantomData }
+                }
+            }
+
+            impl<MODE> Sealed for $PTXx<MODE> {}
+
+            impl<MODE> BusPins for $PTXx<Input<MODE>> {
+                type Gpio = $PTX;
+
+                fn port(&self) -> Port {
+                    Port::$Port
+                }
+
+                fn mask(&self) -> u32 {
+                    1 << self.i
+                }
+            }
+
+            impl<MODE> BusPins for $PTXx<Output<MODE>> {
+                type Gpio = $PTX;
+
+                fn port(&self) -> Port {
+                    Port::$Port
+                }
+
+                fn mask(&self) -> u32 {
+                    1 << self.i
+                }
+            }
+
+            impl<MODE> OutputBusPins for $PTXx<Output<MODE>> {}
+
+            impl<MODE> StatefulOutputPin for $PTXx<Output<MODE>> {
+                fn is_set_high(&self) -> bool {
+                    !self.is_set_low()
+                }
+
+                fn is_set_low(&self) -> bool {
+                    (PDOR { _0: () }).pdor().read().bits() & (1 << self.i) == 0
+                }
+            }
+
+            impl<MODE> OutputPin for $PTXx<Output<MODE>> {
+                fn set_high(&mut self) {
+                    (PSOR { _0: () }).psor().write(|w| unsafe { w.bits(1 << self.i) })
+                }
+
+                fn set_low(&mut self) {
+                    (PCOR { _0: () }).pcor().write(|w| unsafe { w.bits(1 << self.i) })
+                }
+            }
+
+            impl<MODE> ToggleableOutputPin for $PTXx<Output<MODE>> {
+                fn toggle(&mut self) {
+                    (PTOR { _0: () }).ptor().write(|w| unsafe { w.bits(1 << self.i) })
+                }
+            }
+
+            impl<MODE> InputPin for $PTXx<Input<MODE>> {
+                fn is_high(&self) -> bool {
+                    !self.is_low()
+                }
+
+                fn is_low(&self) -> bool {
+                    (PDIR { _0: () }).pdir().read().bits() & (1 << self.i) == 0
+                }
             }
 
             // This pin owns its section of the PDOR, PSOR, PCOR, PTOR, and PDIR registers, as well
             // as its PCR register
             // Reference: 11.14.1 Pin Control Register n (PORTx_PCRn)
             $(
+                $(#[$attr])*
                 #[doc = "General Purpose Input/Output Port "]
                 #[doc = $docport]
                 #[doc = " Pin "]
@@ -357,12 +881,32 @@ macro_rules! gpio {
                     _mode: PhantomData<MODE>,
                 }
 
+                $(#[$attr])*
+                impl<MODE> Sealed for $PTXi<MODE> {}
+
+                $(#[$attr])*
                 impl<MODE> $PTXi<MODE> {
+                    /// Erases the pin number from the type
+                    pub fn downgrade(self) -> $PTXx<MODE> {
+                        $PTXx { i: $i, _mode: PhantomData }
+                    }
+
                     pub fn into_alternate_alt0(self, pcr: &mut PCR) -> $PTXi<Alternate<ALT0>> {
                         set_pin_mux($i, pcr, PinMux::ALT0);
                         $PTXi { _mode: PhantomData }
                     }
 
+                    /// Disables the pin, with the pull resistors and requests off
+                    pub fn into_disabled(self, pcr: &mut PCR) -> $PTXi<Alternate<ALT0>> {
+                        self.into_alternate_alt0(pcr)
+                    }
+
+                    /// Configures the pin for its analog function, with the pull resistors and
+                    /// requests off
+                    pub fn into_analog(self, pcr: &mut PCR) -> $PTXi<Alternate<ALT0>> {
+                        self.into_alternate_alt0(pcr)
+                    }
+
                     pub fn into_alternate_alt1(self, pcr: &mut PCR) -> $PTXi<Alternate<ALT1>> {
                         set_pin_mux($i, pcr, PinMux::ALT1);
                         $PTXi { _mode: PhantomData }
@@ -403,8 +947,207 @@ macro_rules! gpio {
                         set_pin_mode($i, pddr, PinMode::Output);
                         $PTXi { _mode: PhantomData }
                     }
+
+                    pub fn into_open_drain_output(self, pcr: &mut PCR, pddr: &mut PDDR) -> $PTXi<Output<OpenDrain>> {
+                        set_pin_mux($i, pcr, PinMux::ALT1);
+                        set_pin_open_drain($i, pcr, true);
+                        set_pin_mode($i, pddr, PinMode::Output);
+                        $PTXi { _mode: PhantomData }
+                    }
+
+                    /// Configures the pin as a floating input that can be switched to an output at
+                    /// runtime
+                    pub fn into_dynamic(self, pcr: &mut PCR, pddr: &mut PDDR) -> $PTXi<Dynamic> {
+                        set_pin_mux($i, pcr, PinMux::ALT1);
+                        set_pin_mode($i, pddr, PinMode::Input);
+                        $PTXi { _mode: PhantomData }
+                    }
+
+                    pub fn into_floating_input(self, pcr: &mut PCR, pddr: &mut PDDR) -> $PTXi<Input<Floating>> {
+                        set_pin_mux($i, pcr, PinMux::ALT1);
+                        set_pin_pull($i, pcr, PinPull::None);
+                        set_pin_mode($i, pddr, PinMode::Input);
+                        $PTXi { _mode: PhantomData }
+                    }
+
+                    pub fn into_pull_up_input(self, pcr: &mut PCR, pddr: &mut PDDR) -> $PTXi<Input<PullUp>> {
+                        set_pin_mux($i, pcr, PinMux::ALT1);
+                        set_pin_pull($i, pcr, PinPull::Up);
+                        set_pin_mode($i, pddr, PinMode::Input);
+                        $PTXi { _mode: PhantomData }
+                    }
+
+                    pub fn into_pull_down_input(self, pcr: &mut PCR, pddr: &mut PDDR) -> $PTXi<Input<PullDown>> {
+                        set_pin_mux($i, pcr, PinMux::ALT1);
+                        set_pin_pull($i, pcr, PinPull::Down);
+                        set_pin_mode($i, pddr, PinMode::Input);
+                        $PTXi { _mode: PhantomData }
+                    }
+                }
+
+                $(#[$attr])*
+                impl<MODE> $PTXi<Input<MODE>> {
+                    /// Raises a DMA request on every `edge` of this pin
+                    // Reference: 11.14.1 Pin Control Register n (PORTx_PCRn)
+                    pub fn enable_dma_request(&mut self, pcr: &mut PCR, edge: Edge) -> DmaRequest {
+                        let irqc = match edge {
+                            Edge::Rising => $portx::pcr::IRQCW::_0001,
+                            Edge::Falling => $portx::pcr::IRQCW::_0010,
+                            Edge::Either => $portx::pcr::IRQCW::_0011,
+                        };
+                        set_pin_request($i, pcr, irqc);
+                        DmaRequest { _0: () }
+                    }
+
+                    /// Stops raising DMA requests on edges of this pin
+                    pub fn disable_dma_request(&mut self, pcr: &mut PCR) {
+                        set_pin_request($i, pcr, $portx::pcr::IRQCW::_0000);
+                    }
+                }
+
+                // The current mode is read back from the PDDR and PCR registers, so the pin stays
+                // zero-sized
+                $(#[$attr])*
+                impl $PTXi<Dynamic> {
+                    /// Switches the pin to a floating input
+                    pub fn make_input(&mut self, pcr: &mut PCR, pddr: &mut PDDR) {
+                        set_pin_open_drain($i, pcr, false);
+                        set_pin_pull($i, pcr, PinPull::None);
+                        set_pin_mode($i, pddr, PinMode::Input);
+                    }
+
+                    /// Switches the pin to a pulled up input
+                    pub fn make_pull_up_input(&mut self, pcr: &mut PCR, pddr: &mut PDDR) {
+                        set_pin_open_drain($i, pcr, false);
+                        set_pin_pull($i, pcr, PinPull::Up);
+                        set_pin_mode($i, pddr, PinMode::Input);
+                    }
+
+                    /// Switches the pin to a pulled down input
+                    pub fn make_pull_down_input(&mut self, pcr: &mut PCR, pddr: &mut PDDR) {
+                        set_pin_open_drain($i, pcr, false);
+                        set_pin_pull($i, pcr, PinPull::Down);
+                        set_pin_mode($i, pddr, PinMode::Input);
+                    }
+
+                    /// Switches the pin to a push pull output
+                    pub fn make_output(&mut self, pcr: &mut PCR, pddr: &mut PDDR) {
+                        set_pin_open_drain($i, pcr, false);
+                        set_pin_pull($i, pcr, PinPull::None);
+                        set_pin_mode($i, pddr, PinMode::Output);
+                    }
+
+                    /// Switches the pin to an open drain output, its level can still be read
+                    pub fn make_open_drain(&mut self, pcr: &mut PCR, pddr: &mut PDDR) {
+                        set_pin_open_drain($i, pcr, true);
+                        set_pin_pull($i, pcr, PinPull::None);
+                        set_pin_mode($i, pddr, PinMode::Output);
+                    }
+
+                    /// Returns `true` if the pin is currently an output
+                    pub fn is_output(&self) -> bool {
+                        (PDDR { _0: () }).pddr().read().bits() & (1 << $i) != 0
+                    }
+
+                    /// Returns `true` if the pin is currently an open drain output
+                    pub fn is_open_drain(&self) -> bool {
+                        self.is_output() && (PCR { _0: () }).pcr()[$i].read().ode().bit_is_set()
+                    }
+
+                    /// Drives the pin high, fails if the pin is not an output
+                    pub fn set_high(&mut self) -> Result<(), PinModeError> {
+                        if !self.is_output() {
+                            return Err(PinModeError::IncorrectMode);
+                        }
+                        (PSOR { _0: () }).psor().write(|w| unsafe { w.bits(1 << $i) });
+                        Ok(())
+                    }
+
+                    /// Drives the pin low, fails if the pin is not an output
+                    pub fn set_low(&mut self) -> Result<(), PinModeError> {
+                        if !self.is_output() {
+                            return Err(PinModeError::IncorrectMode);
+                        }
+                        (PCOR { _0: () }).pcor().write(|w| unsafe { w.bits(1 << $i) });
+                        Ok(())
+                    }
+
+                    /// Inverts the pin output, fails if the pin is not an output
+                    pub fn toggle(&mut self) -> Result<(), PinModeError> {
+                        if !self.is_output() {
+                            return Err(PinModeError::IncorrectMode);
+                        }
+                        (PTOR { _0: () }).ptor().write(|w| unsafe { w.bits(1 << $i) });
+                        Ok(())
+                    }
+
+                    /// Returns `true` if the pin is driven high, fails if the pin is not an output
+                    pub fn is_set_high(&self) -> Result<bool, PinModeError> {
+                        self.is_set_low().map(|low| !low)
+                    }
+
+                    /// Returns `true` if the pin is driven low, fails if the pin is not an output
+                    pub fn is_set_low(&self) -> Result<bool, PinModeError> {
+                        if !self.is_output() {
+                            return Err(PinModeError::IncorrectMode);
+                        }
+                        Ok((PDOR { _0: () }).pdor().read().bits() & (1 << $i) == 0)
+                    }
+
+                    /// Returns `true` if the pin level is high, the level of an output pin is read
+                    /// back from the pad
+                    pub fn is_high(&self) -> bool {
+                        !self.is_low()
+                    }
+
+                    /// Returns `true` if the pin level is low, the level of an output pin is read
+                    /// back from the pad
+                    // Reference: 49.2.5 Port Data Input Register (GPIOx_PDIR)
+                    pub fn is_low(&self) -> bool {
+                        (PDIR { _0: () }).pdir().read().bits() & (1 << $i) == 0
+                    }
                 }
 
+                $(#[$attr])*
+                impl<MODE> Disable for $PTXi<MODE> {
+                    type Disabled = $PTXi<Alternate<ALT0>>;
+
+                    fn disable(self) -> Self::Disabled {
+                        self.into_disabled(&mut PCR { _0: () })
+                    }
+                }
+
+                $(#[$attr])*
+                impl<MODE> BusPins for $PTXi<Input<MODE>> {
+                    type Gpio = $PTX;
+
+                    fn port(&self) -> Port {
+                        Port::$Port
+                    }
+
+                    fn mask(&self) -> u32 {
+                        1 << $i
+                    }
+                }
+
+                $(#[$attr])*
+                impl<MODE> BusPins for $PTXi<Output<MODE>> {
+                    type Gpio = $PTX;
+
+                    fn port(&self) -> Port {
+                        Port::$Port
+                    }
+
+                    fn mask(&self) -> u32 {
+                        1 << $i
+                    }
+                }
+
+                $(#[$attr])*
+                impl<MODE> OutputBusPins for $PTXi<Output<MODE>> {}
+
+                $(#[$attr])*
+                #[cfg(not(feature = "bitband"))]
                 impl<MODE> StatefulOutputPin for $PTXi<Output<MODE>> {
                     fn is_set_high(&self) -> bool {
                         !self.is_set_low()
@@ -415,6 +1158,8 @@ macro_rules! gpio {
                     }
                 }
 
+                $(#[$attr])*
+                #[cfg(not(feature = "bitband"))]
                 impl<MODE> OutputPin for $PTXi<Output<MODE>> {
                     fn set_high(&mut self) {
                         (PSOR { _0: () }).psor().write(|w| unsafe { w.bits(1 << $i) })
@@ -425,8 +1170,40 @@ macro_rules! gpio {
                     }
                 }
 
-                impl<MODE> toggleable::Default for $PTXi<Output<MODE>> {}
+                $(#[$attr])*
+                // A single word access to the bit-band alias of the pin's bit
+                #[cfg(feature = "bitband")]
+                impl<MODE> StatefulOutputPin for $PTXi<Output<MODE>> {
+                    fn is_set_high(&self) -> bool {
+                        unsafe { bitband::read((PDOR { _0: () }).pdor(), $i) }
+                    }
+
+                    fn is_set_low(&self) -> bool {
+                        !self.is_set_high()
+                    }
+                }
+
+                $(#[$attr])*
+                #[cfg(feature = "bitband")]
+                impl<MODE> OutputPin for $PTXi<Output<MODE>> {
+                    fn set_high(&mut self) {
+                        unsafe { bitband::write((PDOR { _0: () }).pdor(), $i, true) }
+                    }
+
+                    fn set_low(&mut self) {
+                        unsafe { bitband::write((PDOR { _0: () }).pdor(), $i, false) }
+                    }
+                }
+
+                $(#[$attr])*
+                impl<MODE> ToggleableOutputPin for $PTXi<Output<MODE>> {
+                    fn toggle(&mut self) {
+                        (PTOR { _0: () }).ptor().write(|w| unsafe { w.bits(1 << $i) })
+                    }
+                }
 
+                $(#[$attr])*
+                #[cfg(not(feature = "bitband"))]
                 impl<MODE> InputPin for $PTXi<Input<MODE>> {
                     fn is_high(&self) -> bool {
                         !self.is_low()
@@ -436,13 +1213,28 @@ macro_rules! gpio {
                         (PDIR { _0: () }).pdir().read().bits() & (1 << $i) == 0
                     }
                 }
+
+                $(#[$attr])*
+                #[cfg(feature = "bitband")]
+                impl<MODE> InputPin for $PTXi<Input<MODE>> {
+                    fn is_high(&self) -> bool {
+                        unsafe { bitband::read((PDIR { _0: () }).pdir(), $i) }
+                    }
+
+                    fn is_low(&self) -> bool {
+                        !self.is_high()
+                    }
+                }
             )+
         }
     }
 }
 
+// Pins bonded out in every package are always available, the remaining pins are only bonded out
+// in the 100-pin LQFP and 121-pin MAPBGA packages
 // Reference: 10.3.1 K20 Signal Multiplexing and Pin Assignments
-gpio!(PORTA, porta, PTA, pta, gpioa, "A", [
+// Reference: 3.3.9.1 DMA request multiplexer channel sources
+gpio!(PORTA, porta, PTA, pta, gpioa, PTAx, A, "A", 49, [
       PTA0: (pta0, 0, Input<Floating>, "0"),
       PTA1: (pta1, 1, Input<Floating>, "1"),
       PTA2: (pta2, 2, Input<Floating>, "2"),
@@ -451,22 +1243,44 @@ gpio!(PORTA, porta, PTA, pta, gpioa, "A", [
       PTA5: (pta5, 5, Input<Floating>, "5"),
       PTA12: (pta12, 12, Input<Floating>, "12"),
       PTA13: (pta13, 13, Input<Floating>, "13"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTA14: (pta14, 14, Input<Floating>, "14"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTA15: (pta15, 15, Input<Floating>, "15"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTA16: (pta16, 16, Input<Floating>, "16"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTA17: (pta17, 17, Input<Floating>, "17"),
       PTA18: (pta18, 18, Input<Floating>, "18"),
       PTA19: (pta19, 19, Input<Floating>, "19"),
 ]);
 
-gpio!(PORTB, portb, PTB, ptb, gpiob, "B", [
+gpio!(PORTB, portb, PTB, ptb, gpiob, PTBx, B, "B", 50, [
       PTB0: (ptb0, 0, Input<Floating>, "0"),
       PTB1: (ptb1, 1, Input<Floating>, "1"),
       PTB2: (ptb2, 2, Input<Floating>, "2"),
       PTB3: (ptb3, 3, Input<Floating>, "3"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTB9: (ptb9, 9, Input<Floating>, "9"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTB10: (ptb10, 10, Input<Floating>, "10"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTB11: (ptb11, 11, Input<Floating>, "11"),
       PTB16: (ptb16, 16, Input<Floating>, "16"),
       PTB17: (ptb17, 17, Input<Floating>, "17"),
       PTB18: (ptb18, 18, Input<Floating>, "18"),
       PTB19: (ptb19, 19, Input<Floating>, "19"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTB20: (ptb20, 20, Input<Floating>, "20"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTB21: (ptb21, 21, Input<Floating>, "21"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTB22: (ptb22, 22, Input<Floating>, "22"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTB23: (ptb23, 23, Input<Floating>, "23"),
 ]);
 
-gpio!(PORTC, portc, PTC, ptc, gpioc, "C", [
+gpio!(PORTC, portc, PTC, ptc, gpioc, PTCx, C, "C", 51, [
       PTC0: (ptc0, 0, Input<Floating>, "0"),
       PTC1: (ptc1, 1, Input<Floating>, "1"),
       PTC2: (ptc2, 2, Input<Floating>, "2"),
@@ -479,9 +1293,25 @@ gpio!(PORTC, portc, PTC, ptc, gpioc, "C", [
       PTC9: (ptc9, 9, Input<Floating>, "9"),
       PTC10: (ptc10, 10, Input<Floating>, "10"),
       PTC11: (ptc11, 11, Input<Floating>, "11"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTC12: (ptc12, 12, Input<Floating>, "12"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTC13: (ptc13, 13, Input<Floating>, "13"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTC14: (ptc14, 14, Input<Floating>, "14"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTC15: (ptc15, 15, Input<Floating>, "15"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTC16: (ptc16, 16, Input<Floating>, "16"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTC17: (ptc17, 17, Input<Floating>, "17"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTC18: (ptc18, 18, Input<Floating>, "18"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTC19: (ptc19, 19, Input<Floating>, "19"),
 ]);
 
-gpio!(PORTD, portd, PTD, ptd, gpiod, "D", [
+gpio!(PORTD, portd, PTD, ptd, gpiod, PTDx, D, "D", 52, [
       PTD0: (ptd0, 0, Input<Floating>, "0"),
       PTD1: (ptd1, 1, Input<Floating>, "1"),
       PTD2: (ptd2, 2, Input<Floating>, "2"),
@@ -490,9 +1320,125 @@ gpio!(PORTD, portd, PTD, ptd, gpiod, "D", [
       PTD5: (ptd5, 5, Input<Floating>, "5"),
       PTD6: (ptd6, 6, Input<Floating>, "6"),
       PTD7: (ptd7, 7, Input<Floating>, "7"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTD8: (ptd8, 8, Input<Floating>, "8"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTD9: (ptd9, 9, Input<Floating>, "9"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTD10: (ptd10, 10, Input<Floating>, "10"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTD11: (ptd11, 11, Input<Floating>, "11"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTD12: (ptd12, 12, Input<Floating>, "12"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTD13: (ptd13, 13, Input<Floating>, "13"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTD14: (ptd14, 14, Input<Floating>, "14"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTD15: (ptd15, 15, Input<Floating>, "15"),
 ]);
 
-gpio!(PORTE, porte, PTE, pte, gpioe, "E", [
+gpio!(PORTE, porte, PTE, pte, gpioe, PTEx, E, "E", 53, [
       PTE0: (pte0, 0, Input<Floating>, "0"),
       PTE1: (pte1, 1, Input<Floating>, "1"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE2: (pte2, 2, Input<Floating>, "2"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE3: (pte3, 3, Input<Floating>, "3"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE4: (pte4, 4, Input<Floating>, "4"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE5: (pte5, 5, Input<Floating>, "5"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE6: (pte6, 6, Input<Floating>, "6"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE7: (pte7, 7, Input<Floating>, "7"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE8: (pte8, 8, Input<Floating>, "8"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE9: (pte9, 9, Input<Floating>, "9"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE10: (pte10, 10, Input<Floating>, "10"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE11: (pte11, 11, Input<Floating>, "11"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE12: (pte12, 12, Input<Floating>, "12"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE24: (pte24, 24, Input<Floating>, "24"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE25: (pte25, 25, Input<Floating>, "25"),
+      #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+      PTE26: (pte26, 26, Input<Floating>, "26"),
 ]);
+
+#[cfg(test)]
+mod tests {
+    use core::cell::UnsafeCell;
+    use core::mem;
+
+    use mk20d7::{sim::SCGC5, PORTA, PORTB, PORTC, PORTD, PORTE, PTA, PTB, PTC, PTD, PTE};
+
+    use super::GpioExt;
+
+    // Reset value of SIM_SCGC5, the LPTMR and TSI clocks are enabled on top of it by the tests
+    // Reference: 12.2.12 System Clock Gating Control Register 5 (SIM_SCGC5)
+    const SCGC5_RESET: u32 = 0x0004_0182;
+    const SCGC5_LPTMR: u32 = 1 << 0;
+    const SCGC5_TSI: u32 = 1 << 5;
+
+    // RAM backed SIM_SCGC5 register, splitting a port only touches the clock gating register
+    struct MockScgc5 {
+        register: UnsafeCell<u32>,
+    }
+
+    impl MockScgc5 {
+        fn new(value: u32) -> Self {
+            MockScgc5 { register: UnsafeCell::new(value) }
+        }
+
+        fn scgc5(&self) -> &SCGC5 {
+            unsafe { &*(self.register.get() as *const SCGC5) }
+        }
+    }
+
+    // The peripheral singletons are zero-sized, taking them this way skips the `Peripherals`
+    // bookkeeping that is not available on the host
+    fn take<T>() -> T {
+        unsafe { mem::zeroed() }
+    }
+
+    #[test]
+    fn split_keeps_other_clock_gates_enabled() {
+        let mock = MockScgc5::new(SCGC5_RESET | SCGC5_LPTMR | SCGC5_TSI);
+        let scgc5 = mock.scgc5();
+
+        (take::<PTA>(), take::<PORTA>()).split(scgc5);
+        (take::<PTB>(), take::<PORTB>()).split(scgc5);
+
+        let r = scgc5.read();
+        assert!(r.porta().bit_is_set());
+        assert!(r.portb().bit_is_set());
+        assert!(r.lptimer().bit_is_set());
+        assert!(r.tsi().bit_is_set());
+    }
+
+    #[test]
+    fn split_every_port_enables_every_port_clock_gate() {
+        let mock = MockScgc5::new(SCGC5_RESET);
+        let scgc5 = mock.scgc5();
+
+        (take::<PTE>(), take::<PORTE>()).split(scgc5);
+        (take::<PTC>(), take::<PORTC>()).split(scgc5);
+        (take::<PTA>(), take::<PORTA>()).split(scgc5);
+        (take::<PTD>(), take::<PORTD>()).split(scgc5);
+        (take::<PTB>(), take::<PORTB>()).split(scgc5);
+
+        let r = scgc5.read();
+        assert!(r.porta().bit_is_set());
+        assert!(r.portb().bit_is_set());
+        assert!(r.portc().bit_is_set());
+        assert!(r.portd().bit_is_set());
+        assert!(r.porte().bit_is_set());
+        assert_eq!(r.bits() & SCGC5_RESET, SCGC5_RESET);
+    }
+}
@@ -2,6 +2,16 @@
 #![feature(never_type)]
 #![feature(try_from)]
 
+#[cfg(any(
+    all(feature = "lqfp64", feature = "mapbga64"),
+    all(feature = "lqfp64", feature = "lqfp100"),
+    all(feature = "lqfp64", feature = "mapbga121"),
+    all(feature = "mapbga64", feature = "lqfp100"),
+    all(feature = "mapbga64", feature = "mapbga121"),
+    all(feature = "lqfp100", feature = "mapbga121"),
+))]
+compile_error!("Only one package feature can be enabled");
+
 extern crate cortex_m;
 extern crate embedded_hal as hal;
 pub extern crate mk20d7;
@@ -11,11 +21,18 @@ extern crate bit_field;
 extern crate void;
 extern crate nb;
 
+pub mod bitband;
 pub mod delay;
+pub mod dma;
 pub mod gpio;
 pub mod mcg;
 pub mod osc;
 pub mod prelude;
 pub mod serial;
+pub mod signals;
 pub mod sim;
 pub mod wdog;
+
+mod sealed {
+    pub trait Sealed {}
+}
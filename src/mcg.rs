@@ -3,7 +3,7 @@ use core::convert::TryFrom;
 use mk20d7::{mcg::RegisterBlock, mcg::c1};
 
 use sim::MAXIMUM_CLOCK_FREQUENCY;
-use bitrate::{U32BitrateExt, KiloHertz, MegaHertz};
+use bitrate::{U32BitrateExt, Hertz, KiloHertz, MegaHertz};
 
 pub const FLL_RANGE_MIN: f32 = 31.25;
 pub const FLL_RANGE_MAX: f32 = 39.0625;
@@ -13,6 +13,10 @@ pub const PLL_DIVIDER_NUMERATOR_MAX: u8 = 55;
 pub const PLL_DIVIDER_DENOMINATOR_MIN: u8 = 1;
 pub const PLL_DIVIDER_DENOMINATOR_MAX: u8 = 25;
 
+pub const SLOW_INTERNAL_REFERENCE_FREQUENCY: u32 = 32_768;
+pub const FAST_INTERNAL_REFERENCE_FREQUENCY: u32 = 4_000_000;
+pub const RTC_OSCILLATOR_FREQUENCY: u32 = 32_768;
+
 pub struct MultipurposeClockGenerator<'a> {
     mcg: &'a RegisterBlock,
     pub external_crystal_frequency: MegaHertz<u32>,
@@ -182,6 +186,60 @@ impl<'a> MultipurposeClockGenerator<'a> {
         ((num * self.external_crystal_frequency.0) / den).mhz()
     }
 
+    // Frequency of MCGOUTCLK, the clock divided by the SIM into the core, bus and flash clocks
+    // Reference: 24.4.1 MCG mode state diagram
+    pub fn get_output_frequency(&self) -> Hertz<u32> {
+        let external_crystal_frequency: Hertz<u32> = self.external_crystal_frequency.into();
+
+        let external_reference_frequency = if self.mcg.c7.read().oscsel().bit_is_set() {
+            RTC_OSCILLATOR_FREQUENCY
+        } else {
+            external_crystal_frequency.0
+        };
+
+        match self.mcg.c1.read().clks() {
+            c1::CLKSR::_00 if self.mcg.c6.read().plls().bit_is_set() => {
+                // Reference: 24.3.5 MCG Control 5 Register (MCG_C5)
+                // Reference: 24.3.6 MCG Control 6 Register (MCG_C6)
+                let (numerator, denominator) = self.get_pll_frequency_divider();
+                (external_crystal_frequency.0 * u32::from(numerator) / u32::from(denominator)).hz()
+            },
+            c1::CLKSR::_00 => {
+                let reference_frequency = if self.mcg.c1.read().irefs().bit_is_set() {
+                    SLOW_INTERNAL_REFERENCE_FREQUENCY
+                } else {
+                    external_reference_frequency / u32::from(self.get_external_crystal_frequency_divider())
+                };
+
+                // Reference: 24.3.4 MCG Control 4 Register (MCG_C4)
+                let c4 = self.mcg.c4.read();
+                let factor = match (c4.dmx32().bit_is_set(), c4.drst_drs().bits()) {
+                    (false, 0) => 640,
+                    (false, 1) => 1280,
+                    (false, 2) => 1920,
+                    (false, _) => 2560,
+                    (true, 0) => 732,
+                    (true, 1) => 1464,
+                    (true, 2) => 2197,
+                    (true, _) => 2929,
+                };
+
+                (reference_frequency * factor).hz()
+            },
+            c1::CLKSR::_01 => {
+                // Reference: 24.3.2 MCG Control 2 Register (MCG_C2)
+                // Reference: 24.3.8 MCG Status and Control Register (MCG_SC)
+                if self.mcg.c2.read().ircs().bit_is_set() {
+                    (FAST_INTERNAL_REFERENCE_FREQUENCY >> self.mcg.sc.read().fcrdiv().bits()).hz()
+                } else {
+                    SLOW_INTERNAL_REFERENCE_FREQUENCY.hz()
+                }
+            },
+            c1::CLKSR::_10 => external_reference_frequency.hz(),
+            c1::CLKSR::_11 => panic!("Invalid MCG clock source"),
+        }
+    }
+
     pub fn enable_pll(&mut self) {
         self.mcg.c6.write(|w| w.plls().set_bit());
         while self.mcg.s.read().pllst().bit_is_clear() {} // Wait for PLL to be enabled
@@ -1,2 +1,4 @@
+pub use dma::Channel as _mk20d7_hal_dma_Channel;
+pub use dma::DmaExt as _mk20d7_hal_dma_DmaExt;
 pub use gpio::GpioExt as _mk20d7_hal_gpio_GpioExt;
 pub use hal::prelude::*;
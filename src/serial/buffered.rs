@@ -0,0 +1,254 @@
+use core::marker::PhantomData;
+
+use nb;
+use hal::serial;
+use mk20d7::{UART0, UART1, UART2, UART3, UART4};
+
+use super::{Error, Event, Rx, Serial, Tx};
+
+// Ring buffer of bytes over a borrowed slice, every byte of the slice can be used
+struct RingBuffer<'a> {
+    buffer: &'a mut [u8],
+    start: usize,
+    len: usize,
+}
+
+impl<'a> RingBuffer<'a> {
+    fn new(buffer: &'a mut [u8]) -> Self {
+        if buffer.is_empty() {
+            panic!("Invalid ring buffer size: {}", buffer.len());
+        }
+
+        RingBuffer { buffer, start: 0, len: 0 }
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn free_space(&self) -> usize {
+        self.buffer.len() - self.len
+    }
+
+    fn push(&mut self, byte: u8) -> Result<(), u8> {
+        if self.free_space() == 0 {
+            return Err(byte);
+        }
+
+        let end = (self.start + self.len) % self.buffer.len();
+        self.buffer[end] = byte;
+        self.len += 1;
+        Ok(())
+    }
+
+    fn peek(&self) -> Option<u8> {
+        if self.len == 0 {
+            return None;
+        }
+
+        Some(self.buffer[self.start])
+    }
+
+    fn pop(&mut self) -> Option<u8> {
+        let byte = self.peek()?;
+        self.start = (self.start + 1) % self.buffer.len();
+        self.len -= 1;
+        Some(byte)
+    }
+
+    fn into_inner(self) -> &'a mut [u8] {
+        self.buffer
+    }
+}
+
+/// Interrupt driven serial with TX and RX ring buffers
+///
+/// `on_interrupt` must be called from the UART status interrupt handler, the serial is shared
+/// between that handler and the rest of the program with a critical section mutex or a RTIC
+/// resource
+pub struct BufferedSerial<UART, PINS> {
+    serial: Serial<UART, PINS>,
+    tx: RingBuffer<'static>,
+    rx: RingBuffer<'static>,
+    error: Option<Error>,
+}
+
+impl<UART, PINS> BufferedSerial<UART, PINS> {
+    /// Returns the number of received bytes waiting to be read
+    pub fn available(&self) -> usize {
+        self.rx.len()
+    }
+
+    /// Returns the number of bytes that can be written without blocking
+    pub fn free_space(&self) -> usize {
+        self.tx.free_space()
+    }
+}
+
+macro_rules! buffered {
+    ($(
+        $UARTX:ident,
+    )+) => {
+        $(
+            impl<PINS> BufferedSerial<$UARTX, PINS> {
+                /// Takes over a serial and the buffers, and starts listening for received data
+                pub fn new(
+                    mut serial: Serial<$UARTX, PINS>,
+                    tx_buffer: &'static mut [u8],
+                    rx_buffer: &'static mut [u8],
+                ) -> Self {
+                    // The idle line flushes the words below the RX FIFO watermark
+                    serial.listen(Event::Rxne);
+                    serial.listen(Event::Idle);
+
+                    BufferedSerial {
+                        serial,
+                        tx: RingBuffer::new(tx_buffer),
+                        rx: RingBuffer::new(rx_buffer),
+                        error: None,
+                    }
+                }
+
+                /// Stops listening for interrupts, and releases the serial and the buffers
+                pub fn free(mut self) -> (Serial<$UARTX, PINS>, &'static mut [u8], &'static mut [u8]) {
+                    self.serial.unlisten(Event::Rxne);
+                    self.serial.unlisten(Event::Idle);
+                    self.serial.unlisten(Event::Txe);
+
+                    (self.serial, self.tx.into_inner(), self.rx.into_inner())
+                }
+
+                /// Moves the received bytes into the RX buffer and the TX buffer into the
+                /// transmitter, must be called from the UART status interrupt handler
+                pub fn on_interrupt(&mut self) {
+                    let mut rx = Rx::<$UARTX> { _uart: PhantomData, error: None };
+                    loop {
+                        match rx.read_word() {
+                            Ok(word) => {
+                                if self.rx.push(word.data as u8).is_err() || word.overrun {
+                                    self.error = Some(Error::Overrun);
+                                }
+                            },
+                            Err(nb::Error::WouldBlock) => {
+                                // A word received since the last read is kept for the next loop
+                                if rx.clear_idle_if_empty() {
+                                    break;
+                                }
+                            },
+                            Err(nb::Error::Other(flags)) => {
+                                // An error not read yet is only replaced by another error
+                                if let Some(error) = flags.error() {
+                                    self.error = Some(error);
+                                }
+                            },
+                        }
+                    }
+
+                    let mut tx = Tx::<$UARTX> { _uart: PhantomData };
+                    while let Some(byte) = self.tx.peek() {
+                        match serial::Write::<u8>::write(&mut tx, byte) {
+                            Ok(()) => {
+                                self.tx.pop();
+                            },
+                            Err(_) => break,
+                        }
+                    }
+
+                    if self.tx.len() == 0 {
+                        tx.unlisten(Event::Txe);
+                    }
+                }
+            }
+
+            impl<PINS> serial::Read<u8> for BufferedSerial<$UARTX, PINS> {
+                type Error = Error;
+
+                /// Reads a byte from the RX buffer, reporting first any error that happened since
+                /// the last read
+                fn read(&mut self) -> nb::Result<u8, Error> {
+                    if let Some(error) = self.error.take() {
+                        return Err(nb::Error::Other(error));
+                    }
+
+                    self.rx.pop().ok_or(nb::Error::WouldBlock)
+                }
+            }
+
+            impl<PINS> serial::Write<u8> for BufferedSerial<$UARTX, PINS> {
+                type Error = Error;
+
+                /// Waits for the TX buffer to be sent
+                fn flush(&mut self) -> nb::Result<(), Error> {
+                    if self.tx.len() != 0 {
+                        return Err(nb::Error::WouldBlock);
+                    }
+
+                    serial::Write::<u8>::flush(&mut Tx::<$UARTX> { _uart: PhantomData })
+                }
+
+                /// Writes a byte into the TX buffer
+                fn write(&mut self, byte: u8) -> nb::Result<(), Error> {
+                    if self.tx.push(byte).is_err() {
+                        return Err(nb::Error::WouldBlock);
+                    }
+
+                    self.serial.listen(Event::Txe);
+                    Ok(())
+                }
+            }
+        )+
+    }
+}
+
+buffered! {
+    UART0,
+    UART1,
+    UART2,
+    UART3,
+    UART4,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RingBuffer;
+
+    #[test]
+    fn fills_and_drains_in_order() {
+        let mut buffer = [0; 4];
+        let mut ring = RingBuffer::new(&mut buffer);
+
+        for byte in 1..=4 {
+            ring.push(byte).unwrap();
+        }
+        assert_eq!(ring.len(), 4);
+        assert_eq!(ring.free_space(), 0);
+        assert_eq!(ring.push(5), Err(5));
+
+        for byte in 1..=4 {
+            assert_eq!(ring.pop(), Some(byte));
+        }
+        assert_eq!(ring.pop(), None);
+        assert_eq!(ring.free_space(), 4);
+    }
+
+    #[test]
+    fn wraps_around() {
+        let mut buffer = [0; 3];
+        let mut ring = RingBuffer::new(&mut buffer);
+
+        for round in 0..10 {
+            ring.push(round).unwrap();
+            ring.push(round + 100).unwrap();
+            assert_eq!(ring.peek(), Some(round));
+            assert_eq!(ring.pop(), Some(round));
+            assert_eq!(ring.pop(), Some(round + 100));
+            assert_eq!(ring.len(), 0);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_empty_buffer() {
+        RingBuffer::new(&mut []);
+    }
+}
@@ -0,0 +1,219 @@
+use core::ptr;
+use core::sync::atomic::{self, Ordering};
+
+use cortex_m::interrupt;
+use mk20d7::{UART0, UART1, UART2};
+
+use dma::{Channel, DmaSource, Transfer, TransferSize};
+use sealed::Sealed;
+
+use super::{Event, Rx, Tx};
+
+// Reference: 21.3.23 TCD Current Minor Loop Link, Major Loop Count (DMA_TCDn_CITER_ELINKNO)
+const TRANSFER_LEN_MAX: usize = 0x7FFF;
+
+fn check_transfer_len(len: usize) {
+    if len == 0 || len > TRANSFER_LEN_MAX {
+        panic!("Invalid UART DMA transfer length: {}", len);
+    }
+}
+
+/// Circular DMA reception, the DMA channel keeps writing the received bytes around the buffer
+///
+/// The receiver raises the idle interrupt once the line goes idle, which marks the end of a
+/// message
+pub struct CircularRx<UART, CHANNEL> {
+    channel: CHANNEL,
+    buffer: &'static mut [u8],
+    read: usize,
+    rx: Rx<UART>,
+}
+
+macro_rules! serial_dma {
+    ($(
+        $UARTX:ident: ($uartX:ident, $rxsource:expr, $txsource:expr),
+    )+) => {
+        $(
+            impl Sealed for Tx<$UARTX> {}
+
+            impl DmaSource for Tx<$UARTX> {
+                const SOURCE: u8 = $txsource;
+
+                fn disable_dma(&mut self) {
+                    let uart = super::$uartX();
+                    interrupt::free(|_| uart.c2.modify(|_, w| w.tie().clear_bit()));
+                    uart.c5.modify(|_, w| w.tdmas().clear_bit());
+                }
+            }
+
+            impl Sealed for Rx<$UARTX> {}
+
+            impl DmaSource for Rx<$UARTX> {
+                const SOURCE: u8 = $rxsource;
+
+                fn disable_dma(&mut self) {
+                    let uart = super::$uartX();
+                    interrupt::free(|_| uart.c2.modify(|_, w| w.rie().clear_bit()));
+                    uart.c5.modify(|_, w| w.rdmas().clear_bit());
+                }
+            }
+
+            impl Tx<$UARTX> {
+                /// Sends the whole buffer through a DMA channel
+                pub fn write_all_dma<C: Channel>(
+                    self,
+                    buffer: &'static [u8],
+                    mut channel: C,
+                ) -> Transfer<C, &'static [u8], Self> {
+                    check_transfer_len(buffer.len());
+
+                    let uart = super::$uartX();
+                    channel.bind(&self);
+                    channel.set_disable_request_on_completion(true);
+
+                    // The channel reads the buffer once, and writes the data register
+                    let transfer = unsafe {
+                        channel.set_source(buffer.as_ptr() as u32, 1);
+                        channel.set_destination(&uart.d as *const _ as u32, 0);
+                        channel.set_source_last_adjustment(0);
+                        channel.set_destination_last_adjustment(0);
+                        channel.set_transfer_size(TransferSize::Byte, TransferSize::Byte);
+                        channel.set_minor_loop_bytes(1);
+                        channel.set_major_loop_count(buffer.len() as u16);
+
+                        Transfer::start(channel, buffer, self)
+                    };
+
+                    // The transmit data register empty flag requests the transfers
+                    // Reference: 47.3.12 UART Control Register 5 (UART_C5)
+                    uart.c5.modify(|_, w| w.tdmas().set_bit());
+                    interrupt::free(|_| uart.c2.modify(|_, w| w.tie().set_bit()));
+
+                    transfer
+                }
+            }
+
+            impl Rx<$UARTX> {
+                /// Receives bytes through a DMA channel until the buffer is full
+                pub fn read_exact_dma<C: Channel>(
+                    self,
+                    buffer: &'static mut [u8],
+                    mut channel: C,
+                ) -> Transfer<C, &'static mut [u8], Self> {
+                    check_transfer_len(buffer.len());
+
+                    channel.set_disable_request_on_completion(true);
+
+                    // The channel writes the buffer once
+                    let transfer = unsafe {
+                        self.configure_dma(&mut channel, buffer);
+                        channel.set_destination_last_adjustment(0);
+
+                        Transfer::start(channel, buffer, self)
+                    };
+                    Rx::<$UARTX>::enable_dma();
+                    transfer
+                }
+
+                /// Keeps receiving bytes through a DMA channel, wrapping around the buffer
+                pub fn read_circular_dma<C: Channel>(
+                    mut self,
+                    buffer: &'static mut [u8],
+                    mut channel: C,
+                ) -> CircularRx<$UARTX, C> {
+                    check_transfer_len(buffer.len());
+
+                    channel.set_disable_request_on_completion(false);
+                    channel.clear_done();
+                    self.listen(Event::Idle);
+
+                    // The channel moves back to the start of the buffer once it is full, and
+                    // keeps writing it until the circular reception is stopped
+                    unsafe {
+                        self.configure_dma(&mut channel, buffer);
+                        channel.set_destination_last_adjustment(-(buffer.len() as i32));
+
+                        atomic::compiler_fence(Ordering::Release);
+                        channel.enable_request();
+                    }
+                    Rx::<$UARTX>::enable_dma();
+
+                    CircularRx { channel, buffer, read: 0, rx: self }
+                }
+
+                // The destination last adjustment is left to the caller, and must keep the
+                // channel within the buffer
+                unsafe fn configure_dma<C: Channel>(&self, channel: &mut C, buffer: &mut [u8]) {
+                    let uart = super::$uartX();
+                    channel.bind(self);
+                    channel.set_source(&uart.d as *const _ as u32, 0);
+                    channel.set_destination(buffer.as_mut_ptr() as u32, 1);
+                    channel.set_source_last_adjustment(0);
+                    channel.set_transfer_size(TransferSize::Byte, TransferSize::Byte);
+                    channel.set_minor_loop_bytes(1);
+                    channel.set_major_loop_count(buffer.len() as u16);
+                }
+
+                // The receive data register full flag requests the transfers
+                // Reference: 47.3.12 UART Control Register 5 (UART_C5)
+                fn enable_dma() {
+                    let uart = super::$uartX();
+                    uart.c5.modify(|_, w| w.rdmas().set_bit());
+                    interrupt::free(|_| uart.c2.modify(|_, w| w.rie().set_bit()));
+                }
+            }
+
+            impl<C: Channel> CircularRx<$UARTX, C> {
+                /// Copies the bytes received since the last read into `out`, and returns how many
+                /// were copied
+                ///
+                /// Bytes that are not read before the DMA channel wraps around the buffer are
+                /// overwritten
+                pub fn read(&mut self, out: &mut [u8]) -> usize {
+                    atomic::compiler_fence(Ordering::Acquire);
+
+                    let len = self.buffer.len();
+                    let write = (len - usize::from(self.channel.major_loop_count_remaining())) % len;
+
+                    let mut count = 0;
+                    while self.read != write && count < out.len() {
+                        out[count] = unsafe { ptr::read_volatile(&self.buffer[self.read]) };
+                        self.read = (self.read + 1) % len;
+                        count += 1;
+                    }
+                    count
+                }
+
+                /// Returns `true` if the receive line went idle since the last call, which marks
+                /// the end of a message
+                pub fn is_idle(&mut self) -> bool {
+                    if super::$uartX().s1.read().idle().bit_is_clear() {
+                        return false;
+                    }
+
+                    // A pending word is left to the DMA channel, whose read of the data
+                    // register clears the flag as well
+                    self.rx.clear_idle_if_empty();
+                    true
+                }
+
+                /// Stops the reception, and releases the channel, the buffer and the receiver
+                pub fn stop(mut self) -> (C, &'static mut [u8], Rx<$UARTX>) {
+                    self.rx.unlisten(Event::Idle);
+                    self.rx.disable_dma();
+                    self.channel.disable_request();
+                    atomic::compiler_fence(Ordering::Acquire);
+
+                    (self.channel, self.buffer, self.rx)
+                }
+            }
+        )+
+    }
+}
+
+// Reference: 3.3.9.1 DMA request multiplexer channel sources
+serial_dma! {
+    UART0: (uart0, 2, 3),
+    UART1: (uart1, 4, 5),
+    UART2: (uart2, 6, 7),
+}
@@ -0,0 +1,640 @@
+//! ISO-7816 smart card interface on UART0, with the T=0 and T=1 protocols
+
+use core::marker::PhantomData;
+
+use cortex_m::interrupt;
+use bitrate::{Bps, Hertz};
+use hal::serial::{Read, Write};
+use nb;
+use mk20d7::UART0;
+
+use sim::Clocks;
+use super::{BaudRateError, Direction, Rx, Serial, Tx};
+
+/// Largest number of bytes of an answer to reset
+pub const ATR_LEN_MAX: usize = 33;
+
+/// Largest number of bytes of the information field of a T=1 block
+pub const INFORMATION_FIELD_LEN_MAX: usize = 254;
+
+// Default number of repetitions of a character rejected by a NACK
+const NACK_THRESHOLD: u8 = 3;
+
+// Reference: ISO/IEC 7816-3 Table 7, clock rate conversion factor Fi
+const CLOCK_RATE_CONVERSION: [u16; 16] = [
+    372, 372, 558, 744, 1116, 1488, 1860, 0, 0, 512, 768, 1024, 1536, 2048, 0, 0,
+];
+
+// Reference: ISO/IEC 7816-3 Table 8, baud rate adjustment factor Di
+const BAUD_RATE_ADJUSTMENT: [u8; 16] = [0, 1, 2, 4, 8, 16, 32, 64, 12, 20, 0, 0, 0, 0, 0, 0];
+
+/// Smart card error
+#[derive(Debug)]
+pub enum Error {
+    /// Serial error
+    Serial(super::Error),
+
+    /// The baud rate of the card cannot be generated
+    BaudRate(BaudRateError),
+
+    /// The answer to reset is invalid
+    Atr(AtrError),
+
+    /// A transmitted character was rejected by more NACKs than the threshold
+    TransmitThreshold,
+
+    /// A received character was rejected by more NACKs than the threshold
+    ReceiveThreshold,
+
+    /// The card did not respect the guard time
+    GuardTime,
+
+    /// The card did not send a character within the waiting time
+    Timeout,
+
+    /// The information field of a T=1 block does not fit into the buffer
+    BlockLength,
+
+    /// The checksum of a T=1 block is wrong
+    Checksum,
+}
+
+impl From<super::Error> for Error {
+    fn from(error: super::Error) -> Self {
+        Error::Serial(error)
+    }
+}
+
+/// Invalid answer to reset
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AtrError {
+    /// More bytes are needed
+    Truncated,
+
+    /// The initial character is neither the direct nor the inverse convention
+    InvalidInitialCharacter,
+
+    /// The clock rate conversion or the baud rate adjustment factor is reserved
+    InvalidClockRate,
+
+    /// The first protocol offered is neither T=0 nor T=1
+    UnsupportedProtocol,
+
+    /// The check character is wrong
+    Checksum,
+}
+
+/// Coding convention of the characters
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Convention {
+    /// High level is one, least significant bit first
+    Direct,
+
+    /// Low level is one, most significant bit first
+    Inverse,
+}
+
+/// Transmission protocol
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Protocol {
+    /// Character protocol
+    T0,
+
+    /// Block protocol
+    T1,
+}
+
+/// Answer to reset of a card
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Atr {
+    /// Coding convention
+    pub convention: Convention,
+
+    /// First protocol offered by the card
+    pub protocol: Protocol,
+
+    /// Clock rate conversion factor F
+    pub clock_rate_conversion: u16,
+
+    /// Baud rate adjustment factor D
+    pub baud_rate_adjustment: u8,
+
+    /// Extra guard time N, in elementary time units
+    pub extra_guard_time: u8,
+
+    /// Waiting time integer WI of T=0
+    pub waiting_time_integer: u8,
+
+    /// Information field size of the card IFSC of T=1
+    pub information_field_size: u8,
+
+    /// Character waiting time integer CWI of T=1
+    pub character_waiting_time_integer: u8,
+
+    /// Block waiting time integer BWI of T=1
+    pub block_waiting_time_integer: u8,
+
+    historical_bytes: [u8; 15],
+    historical_bytes_len: usize,
+}
+
+impl Atr {
+    /// Parses an answer to reset, `AtrError::Truncated` is returned until every byte is there
+    // Reference: ISO/IEC 7816-3 8.2 Answer-to-Reset
+    pub fn parse(bytes: &[u8]) -> Result<Self, AtrError> {
+        let mut bytes = bytes.iter().cloned();
+        let mut next = || bytes.next().ok_or(AtrError::Truncated);
+
+        let convention = match next()? {
+            0x3B => Convention::Direct,
+            0x3F => Convention::Inverse,
+            _ => return Err(AtrError::InvalidInitialCharacter),
+        };
+
+        let mut atr = Atr {
+            convention,
+            protocol: Protocol::T0,
+            clock_rate_conversion: 372,
+            baud_rate_adjustment: 1,
+            extra_guard_time: 0,
+            waiting_time_integer: 10,
+            information_field_size: 32,
+            character_waiting_time_integer: 13,
+            block_waiting_time_integer: 4,
+            historical_bytes: [0; 15],
+            historical_bytes_len: 0,
+        };
+
+        // The format byte and every interface byte are covered by the check character
+        let format = next()?;
+        let mut check = format;
+        let mut indicator = format >> 4;
+        let mut group = 1;
+        let mut group_protocol = None;
+        let mut first_protocol = None;
+        let mut check_character = false;
+
+        loop {
+            let mut interface = [None; 3];
+            for (bit, byte) in interface.iter_mut().enumerate() {
+                if indicator & (1 << bit) != 0 {
+                    let value = next()?;
+                    check ^= value;
+                    *byte = Some(value);
+                }
+            }
+
+            match (group, group_protocol, interface) {
+                (1, _, [ta, _, tc]) => {
+                    if let Some(ta) = ta {
+                        atr.clock_rate_conversion = CLOCK_RATE_CONVERSION[usize::from(ta >> 4)];
+                        atr.baud_rate_adjustment = BAUD_RATE_ADJUSTMENT[usize::from(ta & 0xF)];
+                        if atr.clock_rate_conversion == 0 || atr.baud_rate_adjustment == 0 {
+                            return Err(AtrError::InvalidClockRate);
+                        }
+                    }
+
+                    if let Some(tc) = tc {
+                        atr.extra_guard_time = tc;
+                    }
+                },
+                (2, Some(0), [_, _, Some(tc)]) => atr.waiting_time_integer = tc,
+                (3..=8, Some(1), [ta, tb, _]) => {
+                    if let Some(ta) = ta {
+                        atr.information_field_size = ta;
+                    }
+
+                    if let Some(tb) = tb {
+                        atr.character_waiting_time_integer = tb & 0xF;
+                        atr.block_waiting_time_integer = tb >> 4;
+                    }
+                },
+                _ => {},
+            }
+
+            if indicator & 0x8 == 0 {
+                break;
+            }
+
+            let td = next()?;
+            check ^= td;
+            let protocol = td & 0xF;
+            check_character |= protocol != 0;
+            first_protocol = first_protocol.or(Some(protocol));
+            group_protocol = Some(protocol);
+            indicator = td >> 4;
+            group += 1;
+        }
+
+        atr.protocol = match first_protocol.unwrap_or(0) {
+            0 => Protocol::T0,
+            1 => Protocol::T1,
+            _ => return Err(AtrError::UnsupportedProtocol),
+        };
+
+        atr.historical_bytes_len = usize::from(format & 0xF);
+        for byte in atr.historical_bytes[..atr.historical_bytes_len].iter_mut() {
+            *byte = next()?;
+            check ^= *byte;
+        }
+
+        // The check character is only sent when a protocol other than T=0 is indicated
+        if check_character && check != next()? {
+            return Err(AtrError::Checksum);
+        }
+
+        Ok(atr)
+    }
+
+    /// Returns the historical bytes
+    pub fn historical_bytes(&self) -> &[u8] {
+        &self.historical_bytes[..self.historical_bytes_len]
+    }
+}
+
+/// Prologue of a T=1 block
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Block {
+    /// Node address
+    pub nad: u8,
+
+    /// Protocol control byte
+    pub pcb: u8,
+
+    /// Length of the information field
+    pub len: usize,
+}
+
+// Longitudinal redundancy check of a T=1 block, the exclusive-or of all its bytes
+fn lrc(bytes: &[u8]) -> u8 {
+    bytes.iter().fold(0, |lrc, byte| lrc ^ byte)
+}
+
+/// ISO-7816 smart card interface
+///
+/// The card I/O line is either wired to both the TX and RX pins, or to the TX pin of a single wire
+/// half-duplex serial. The card clock and reset are driven separately
+pub struct SmartCard<PINS> {
+    serial: Serial<UART0, PINS>,
+    rx: Rx<UART0>,
+    clocks: Clocks,
+    protocol: Protocol,
+}
+
+impl<PINS> Serial<UART0, PINS> {
+    /// Turns the serial into a smart card interface, ready to receive the answer to reset
+    ///
+    /// The serial must run at the initial baud rate of the card, its clock frequency divided by
+    /// 372
+    pub fn into_smart_card(self, clocks: Clocks) -> SmartCard<PINS> {
+        let rx = Rx { _uart: PhantomData, error: None };
+        let mut smart_card = SmartCard { serial: self, rx, clocks, protocol: Protocol::T0 };
+        smart_card.enable();
+        smart_card
+    }
+}
+
+impl<PINS> SmartCard<PINS> {
+    /// Releases the serial, with the ISO-7816 mode disabled
+    pub fn free(self) -> Serial<UART0, PINS> {
+        self.set_enabled(|uart| uart.c7816.reset());
+        self.serial
+    }
+
+    /// Receives the answer to reset into `buffer`, and parses it
+    ///
+    /// The coding convention is detected from the initial character. Must be called before the
+    /// card reset is released
+    pub fn read_atr(&mut self, buffer: &mut [u8; ATR_LEN_MAX]) -> Result<Atr, Error> {
+        // The initial character is not stored in the receive buffer, the convention it carries
+        // is applied to S2 MSBF and RXINV and to C3 TXINV instead
+        // Reference: 47.3.23 UART 7816 Control Register (UART_C7816)
+        // Reference: 47.3.25 UART 7816 Interrupt Status Register (UART_IS7816)
+        self.set_enabled(|uart| uart.c7816.modify(|_, w| w.init().set_bit()));
+        self.clear_status();
+
+        while self.serial.uart.is7816.read().initd().bit_is_clear() {
+            self.check_status()?;
+        }
+        self.serial.uart.is7816.write(|w| w.initd().set_bit());
+
+        // Reference: 47.3.6 UART Status Register 2 (UART_S2)
+        buffer[0] = if self.serial.uart.s2.read().msbf().bit_is_set() { 0x3F } else { 0x3B };
+
+        for len in 2..=ATR_LEN_MAX {
+            buffer[len - 1] = self.read_byte()?;
+
+            match Atr::parse(&buffer[..len]) {
+                Err(AtrError::Truncated) => {},
+                result => return result.map_err(Error::Atr),
+            }
+        }
+        Err(Error::Atr(AtrError::Truncated))
+    }
+
+    /// Applies the parameters of the answer to reset: the baud rate, the guard time, the protocol
+    /// and its waiting times
+    pub fn configure(&mut self, atr: &Atr, card_clock: Hertz<u32>) -> Result<(), Error> {
+        let baud_rate = card_clock.0 / u32::from(atr.clock_rate_conversion) * u32::from(atr.baud_rate_adjustment);
+        self.serial.set_baud_rate(Bps(baud_rate), self.clocks).map_err(Error::BaudRate)?;
+        self.set_guard_time(atr.extra_guard_time);
+
+        let uart = &self.serial.uart;
+        let protocol = atr.protocol;
+        let baud_rate_adjustment = atr.baud_rate_adjustment;
+        self.set_enabled(|uart| {
+            // Reference: 47.3.23 UART 7816 Control Register (UART_C7816)
+            uart.c7816.modify(|_, w| w.ttype().bit(protocol == Protocol::T1));
+
+            // The waiting times are multiples of the baud rate adjustment factor
+            // Reference: 47.3.29 UART 7816 Wait FD Register (UART_WF7816)
+            uart.wf7816.write(|w| unsafe { w.gtfd().bits(baud_rate_adjustment) });
+        });
+
+        // The T=1 wait parameters share the address of the T=0 one
+        // Reference: 47.3.26 UART 7816 Wait Parameter Register (UART_WP7816T0)
+        // Reference: 47.3.27 UART 7816 Wait Parameter Register (UART_WP7816T1)
+        let wait_parameter = match protocol {
+            Protocol::T0 => atr.waiting_time_integer,
+            Protocol::T1 => (atr.character_waiting_time_integer << 4) | (atr.block_waiting_time_integer & 0xF),
+        };
+        uart.wp7816t0.write(|w| unsafe { w.bits(wait_parameter) });
+
+        self.protocol = protocol;
+        Ok(())
+    }
+
+    /// Sets the extra guard time between two characters sent to the card, in elementary time units
+    // Reference: 47.3.28 UART 7816 Wait N Register (UART_WN7816)
+    pub fn set_guard_time(&mut self, extra_guard_time: u8) {
+        self.serial.uart.wn7816.write(|w| unsafe { w.gtn().bits(extra_guard_time) });
+    }
+
+    /// Sets the number of NACKs after which a received or transmitted character fails
+    // Reference: 47.3.30 UART 7816 Error Threshold Register (UART_ET7816)
+    pub fn set_nack_thresholds(&mut self, receive: u8, transmit: u8) {
+        if receive > 0xF {
+            panic!("Invalid ISO-7816 receive NACK threshold: {}", receive);
+        }
+
+        if transmit > 0xF {
+            panic!("Invalid ISO-7816 transmit NACK threshold: {}", transmit);
+        }
+
+        self.serial.uart.et7816.write(|w| unsafe {
+            w.rxthreshold().bits(receive);
+            w.txthreshold().bits(transmit)
+        });
+    }
+
+    /// Sends characters with the T=0 protocol, the characters rejected by a NACK are repeated
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        self.clear_status();
+        self.transmit(bytes)?;
+        self.end_transmission()
+    }
+
+    /// Receives characters with the T=0 protocol, the characters with a parity error are rejected
+    /// by a NACK so the card repeats them
+    pub fn read(&mut self, buffer: &mut [u8]) -> Result<(), Error> {
+        for byte in buffer.iter_mut() {
+            *byte = self.read_byte()?;
+        }
+        Ok(())
+    }
+
+    /// Sends a T=1 block, with its prologue and longitudinal redundancy check
+    pub fn write_block(&mut self, nad: u8, pcb: u8, information: &[u8]) -> Result<(), Error> {
+        if information.len() > INFORMATION_FIELD_LEN_MAX {
+            panic!("Invalid ISO-7816 information field length: {}", information.len());
+        }
+
+        let prologue = [nad, pcb, information.len() as u8];
+        let epilogue = [lrc(&prologue) ^ lrc(information)];
+
+        // The transmit length only counts the information field
+        // Reference: 47.3.31 UART 7816 Transmit Length Register (UART_TL7816)
+        self.serial.uart.tl7816.write(|w| unsafe { w.tlen().bits(information.len() as u8) });
+
+        self.clear_status();
+        self.transmit(&prologue)?;
+        self.transmit(information)?;
+        self.transmit(&epilogue)?;
+        self.end_transmission()
+    }
+
+    /// Receives a T=1 block, its information field into `buffer`, and checks its longitudinal
+    /// redundancy check
+    pub fn read_block(&mut self, buffer: &mut [u8]) -> Result<Block, Error> {
+        let mut prologue = [0; 3];
+        self.read(&mut prologue)?;
+
+        let block = Block { nad: prologue[0], pcb: prologue[1], len: usize::from(prologue[2]) };
+        if block.len > buffer.len() {
+            for _ in 0..=block.len {
+                self.read_byte()?;
+            }
+            return Err(Error::BlockLength);
+        }
+
+        self.read(&mut buffer[..block.len])?;
+
+        let epilogue = self.read_byte()?;
+        if lrc(&prologue) ^ lrc(&buffer[..block.len]) != epilogue {
+            return Err(Error::Checksum);
+        }
+        Ok(block)
+    }
+
+    // The receiver rejects characters with a parity error in T=0, the threshold also applies to
+    // repeated transmissions
+    // Reference: 47.3.23 UART 7816 Control Register (UART_C7816)
+    fn enable(&mut self) {
+        // The characters are 8 data bits with an even parity bit
+        // Reference: 47.3.3 UART Control Register 1 (UART_C1)
+        self.serial.uart.c1.modify(|_, w| {
+            w.m().set_bit();
+            w.pe().set_bit();
+            w.pt().clear_bit()
+        });
+
+        self.set_nack_thresholds(NACK_THRESHOLD, NACK_THRESHOLD);
+        self.set_enabled(|uart| {
+            uart.c7816.write(|w| {
+                w.iso_7816e().set_bit();
+                w.anack().set_bit();
+                w.onack().clear_bit()
+            })
+        });
+    }
+
+    // The ISO-7816 configuration can only be changed while the transmitter and the receiver are
+    // disabled
+    // Reference: 47.3.4 UART Control Register 2 (UART_C2)
+    fn set_enabled<F>(&self, f: F)
+    where
+        F: FnOnce(&UART0),
+    {
+        let uart = &self.serial.uart;
+        while uart.s1.read().tc().bit_is_clear() {}
+
+        interrupt::free(|_| uart.c2.modify(|_, w| w.re().clear_bit().te().clear_bit()));
+        f(uart);
+        interrupt::free(|_| uart.c2.modify(|_, w| w.re().set_bit().te().set_bit()));
+    }
+
+    fn transmit(&mut self, bytes: &[u8]) -> Result<(), Error> {
+        let mut tx = Tx::<UART0> { _uart: PhantomData };
+        tx.set_direction(Direction::Transmit);
+
+        for &byte in bytes {
+            loop {
+                self.check_status()?;
+                match Write::<u8>::write(&mut tx, byte) {
+                    Ok(()) => break,
+                    Err(nb::Error::WouldBlock) => {},
+                    Err(nb::Error::Other(error)) => return Err(Error::Serial(error)),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn end_transmission(&mut self) -> Result<(), Error> {
+        let mut tx = Tx::<UART0> { _uart: PhantomData };
+        loop {
+            self.check_status()?;
+            match Write::<u8>::flush(&mut tx) {
+                Ok(()) => break,
+                Err(nb::Error::WouldBlock) => {},
+                Err(nb::Error::Other(error)) => return Err(Error::Serial(error)),
+            }
+        }
+
+        tx.set_direction(Direction::Receive);
+        Ok(())
+    }
+
+    fn read_byte(&mut self) -> Result<u8, Error> {
+        loop {
+            self.check_status()?;
+            match Read::<u8>::read(&mut self.rx) {
+                Ok(byte) => return Ok(byte),
+                Err(nb::Error::WouldBlock) => {},
+                // Rejected by a NACK in T=0, so repeated by the card
+                Err(nb::Error::Other(super::Error::Parity)) if self.protocol == Protocol::T0 => {},
+                Err(nb::Error::Other(error)) => return Err(Error::Serial(error)),
+            }
+        }
+    }
+
+    // Reference: 47.3.25 UART 7816 Interrupt Status Register (UART_IS7816)
+    fn check_status(&mut self) -> Result<(), Error> {
+        let is7816 = self.serial.uart.is7816.read();
+        let error = if is7816.txt().bit_is_set() {
+            Error::TransmitThreshold
+        } else if is7816.rxt().bit_is_set() {
+            Error::ReceiveThreshold
+        } else if is7816.gtv().bit_is_set() {
+            Error::GuardTime
+        } else if is7816.wt().bit_is_set() || is7816.cwt().bit_is_set() || is7816.bwt().bit_is_set() {
+            Error::Timeout
+        } else {
+            return Ok(());
+        };
+
+        self.clear_status();
+        Err(error)
+    }
+
+    // The flags are cleared by writing ones, the waiting times also elapse between transfers
+    fn clear_status(&mut self) {
+        let is7816 = &self.serial.uart.is7816;
+        let flags = is7816.read().bits();
+        is7816.write(|w| unsafe { w.bits(flags) });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{lrc, Atr, AtrError, Convention, Protocol};
+
+    // Direct convention, TA1, TC1, TC2 for T=0 and 3 historical bytes
+    const T0_ATR: [u8; 9] = [0x3B, 0xD3, 0x96, 0x02, 0x40, 0x14, 0x41, 0x42, 0x43];
+
+    // Inverse convention, TA3 and TB3 for T=1, and the check character
+    const T1_ATR: [u8; 7] = [0x3F, 0x80, 0x81, 0x31, 0xFE, 0x45, 0x8B];
+
+    #[test]
+    fn parses_a_minimal_atr() {
+        let atr = Atr::parse(&[0x3B, 0x00]).unwrap();
+
+        assert_eq!(atr.convention, Convention::Direct);
+        assert_eq!(atr.protocol, Protocol::T0);
+        assert_eq!(atr.clock_rate_conversion, 372);
+        assert_eq!(atr.baud_rate_adjustment, 1);
+        assert_eq!(atr.extra_guard_time, 0);
+        assert_eq!(atr.waiting_time_integer, 10);
+        assert!(atr.historical_bytes().is_empty());
+    }
+
+    #[test]
+    fn parses_a_t0_atr() {
+        let atr = Atr::parse(&T0_ATR).unwrap();
+
+        assert_eq!(atr.convention, Convention::Direct);
+        assert_eq!(atr.protocol, Protocol::T0);
+        assert_eq!(atr.clock_rate_conversion, 512);
+        assert_eq!(atr.baud_rate_adjustment, 32);
+        assert_eq!(atr.extra_guard_time, 2);
+        assert_eq!(atr.waiting_time_integer, 20);
+        assert_eq!(atr.historical_bytes(), b"ABC");
+    }
+
+    #[test]
+    fn parses_a_t1_atr() {
+        let atr = Atr::parse(&T1_ATR).unwrap();
+
+        assert_eq!(atr.convention, Convention::Inverse);
+        assert_eq!(atr.protocol, Protocol::T1);
+        assert_eq!(atr.clock_rate_conversion, 372);
+        assert_eq!(atr.baud_rate_adjustment, 1);
+        assert_eq!(atr.information_field_size, 254);
+        assert_eq!(atr.character_waiting_time_integer, 5);
+        assert_eq!(atr.block_waiting_time_integer, 4);
+        assert!(atr.historical_bytes().is_empty());
+    }
+
+    #[test]
+    fn truncated_atrs() {
+        for len in 0..T0_ATR.len() {
+            assert_eq!(Atr::parse(&T0_ATR[..len]), Err(AtrError::Truncated));
+        }
+
+        for len in 0..T1_ATR.len() {
+            assert_eq!(Atr::parse(&T1_ATR[..len]), Err(AtrError::Truncated));
+        }
+    }
+
+    #[test]
+    fn invalid_atrs() {
+        assert_eq!(Atr::parse(&[0x3A, 0x00]), Err(AtrError::InvalidInitialCharacter));
+
+        // Reserved clock rate conversion factor
+        assert_eq!(Atr::parse(&[0x3B, 0x10, 0x71]), Err(AtrError::InvalidClockRate));
+
+        // T=2 offered first
+        assert_eq!(Atr::parse(&[0x3B, 0x80, 0x02, 0x82]), Err(AtrError::UnsupportedProtocol));
+
+        let mut atr = T1_ATR;
+        atr[6] ^= 0x01;
+        assert_eq!(Atr::parse(&atr), Err(AtrError::Checksum));
+    }
+
+    #[test]
+    fn longitudinal_redundancy_checks() {
+        assert_eq!(lrc(&[]), 0x00);
+        assert_eq!(lrc(&[0x00, 0x00, 0x01, 0x55]), 0x54);
+        assert_eq!(lrc(&[0x00, 0xC1, 0x01, 0xFE, 0x3E]), 0x00);
+    }
+}
@@ -0,0 +1,449 @@
+//! LIN 2.x bus nodes on top of a `Serial`
+
+use core::marker::PhantomData;
+
+use cortex_m::interrupt;
+use cortex_m::peripheral::{DCB, DWT};
+use bitrate::Bps;
+use bit_field::BitField;
+use hal::serial::{Read, Write};
+use nb;
+use mk20d7::{UART0, UART1, UART2, UART3, UART4};
+
+use sim::Clocks;
+use super::{Rx, Serial, Tx};
+
+/// Sync byte sent after the break of every frame header
+pub const SYNC: u8 = 0x55;
+
+/// Largest number of data bytes of a frame response
+pub const RESPONSE_LEN_MAX: usize = 8;
+
+/// Lowest baud rate of a LIN bus, the timeouts are counted in its bit times
+pub const BAUD_RATE_MIN: Bps<u32> = Bps(1_000);
+
+// Longest frame header, 34 bit times with the 40 % margin allowed by LIN
+const HEADER_TIMEOUT: u32 = 48;
+
+// The falling edges of the sync byte are 2 bit times apart, the last one is followed by 2 bit
+// times until the end of the stop bit
+const SYNC_EDGE_TIMEOUT: u32 = 4;
+
+/// LIN error
+#[derive(Debug)]
+pub enum Error {
+    /// Serial error
+    Serial(super::Error),
+
+    /// The byte read back from the bus differs from the byte sent
+    Bit,
+
+    /// The sync byte could not be measured, or its baud rate cannot be generated
+    Sync,
+
+    /// No break was detected within a frame header time
+    Timeout,
+
+    /// The parity bits of the protected identifier are wrong
+    ProtectedId,
+
+    /// The checksum of the response is wrong
+    Checksum,
+}
+
+impl From<super::Error> for Error {
+    fn from(error: super::Error) -> Self {
+        Error::Serial(error)
+    }
+}
+
+/// Checksum model of a frame response
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Checksum {
+    /// Sum of the data bytes, used by LIN 1.x and by the diagnostic frames
+    Classic,
+
+    /// Sum of the protected identifier and the data bytes, used by LIN 2.x
+    Enhanced,
+}
+
+/// Returns the protected identifier of a frame identifier, with its two parity bits
+pub fn protected_id(id: u8) -> u8 {
+    if id > 0x3F {
+        panic!("Invalid LIN frame identifier: {}", id);
+    }
+
+    let p0 = id.get_bit(0) ^ id.get_bit(1) ^ id.get_bit(2) ^ id.get_bit(4);
+    let p1 = !(id.get_bit(1) ^ id.get_bit(3) ^ id.get_bit(4) ^ id.get_bit(5));
+    id | (u8::from(p0) << 6) | (u8::from(p1) << 7)
+}
+
+/// Returns the frame identifier of a protected identifier, if its parity bits are right
+pub fn frame_id(protected_id: u8) -> Option<u8> {
+    let id = protected_id.get_bits(0..6);
+    if self::protected_id(id) == protected_id {
+        Some(id)
+    } else {
+        None
+    }
+}
+
+/// Returns the checksum of a frame response, the inverted sum with carry of its bytes
+pub fn checksum(model: Checksum, protected_id: u8, data: &[u8]) -> u8 {
+    let initial = match model {
+        Checksum::Classic => 0,
+        Checksum::Enhanced => u16::from(protected_id),
+    };
+
+    let sum = data.iter().fold(initial, |sum, &byte| {
+        let sum = sum + u16::from(byte);
+        if sum > 0xFF {
+            sum - 0xFF
+        } else {
+            sum
+        }
+    });
+
+    !(sum as u8)
+}
+
+// Waits until `ready` returns `true`, for at most `bit_times` bit times at the lowest baud rate
+// counted with the DWT cycle counter. Returns `false` on timeout
+fn wait<F: FnMut() -> bool>(clocks: Clocks, bit_times: u32, mut ready: F) -> bool {
+    let timeout = clocks.core().0 / BAUD_RATE_MIN.0 * bit_times;
+    let start = DWT::get_cycle_count();
+    while !ready() {
+        if DWT::get_cycle_count().wrapping_sub(start) > timeout {
+            return false;
+        }
+    }
+    true
+}
+
+fn check_response_len(len: usize) {
+    if len == 0 || len > RESPONSE_LEN_MAX {
+        panic!("Invalid LIN response length: {}", len);
+    }
+}
+
+/// LIN bus node, either master or slave
+///
+/// The transceiver echoes every byte sent back to the receiver, the echo is checked against the
+/// byte sent and then discarded
+///
+/// No byte can be received while break detection is enabled, so it is only enabled between
+/// frames: it is disabled once the break of a header is detected, and enabled again once the
+/// response is written, read or skipped
+///
+/// ```
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::mk20d7::UART0;
+/// # use mk20d7_hal::serial::lin::{Checksum, Error, Lin};
+/// // Slave publishing frame 0x10 and subscribing to frame 0x11
+/// fn slave<PINS>(lin: &mut Lin<UART0, PINS>, data: &mut [u8; 2]) -> Result<(), Error> {
+///     match lin.read_header()? {
+///         0x10 => lin.write_response(0x10, data, Checksum::Enhanced),
+///         0x11 => lin.read_response(0x11, data, Checksum::Enhanced),
+///         _ => {
+///             lin.skip_response();
+///             Ok(())
+///         },
+///     }
+/// }
+///
+/// // Master sending frame 0x11, then reading the response of frame 0x10
+/// fn master<PINS>(lin: &mut Lin<UART0, PINS>, data: &mut [u8; 2]) -> Result<(), Error> {
+///     lin.write_header(0x11)?;
+///     lin.write_response(0x11, data, Checksum::Enhanced)?;
+///
+///     lin.write_header(0x10)?;
+///     lin.read_response(0x10, data, Checksum::Enhanced)
+/// }
+/// ```
+pub struct Lin<UART, PINS> {
+    serial: Serial<UART, PINS>,
+    rx: Rx<UART>,
+    clocks: Clocks,
+}
+
+macro_rules! lin {
+    ($(
+        $UARTX:ident,
+    )+) => {
+        $(
+            impl<PINS> Serial<$UARTX, PINS> {
+                /// Turns the serial into a LIN node, sending 13 bit breaks and detecting breaks
+                ///
+                /// The DWT cycle counter is enabled to measure the sync byte and the timeouts
+                pub fn into_lin(self, clocks: Clocks, dcb: &mut DCB, dwt: &mut DWT) -> Lin<$UARTX, PINS> {
+                    dcb.enable_trace();
+                    dwt.enable_cycle_counter();
+
+                    let rx = Rx { _uart: PhantomData, error: None };
+                    let mut lin = Lin { serial: self, rx, clocks };
+                    lin.set_break(true);
+                    lin.set_break_detection(true);
+                    lin
+                }
+            }
+
+            impl<PINS> Lin<$UARTX, PINS> {
+                /// Releases the serial, with the default break length and break detection disabled
+                pub fn free(mut self) -> Serial<$UARTX, PINS> {
+                    self.set_break_detection(false);
+                    self.set_break(false);
+                    self.serial
+                }
+
+                /// Queues a break after the bytes being transmitted
+                // Reference: 47.3.4 UART Control Register 2 (UART_C2)
+                pub fn send_break(&mut self) {
+                    let uart = &self.serial.uart;
+                    interrupt::free(|_| {
+                        uart.c2.modify(|_, w| w.sbk().set_bit());
+                        uart.c2.modify(|_, w| w.sbk().clear_bit());
+                    });
+                }
+
+                /// Returns `true` once a break has been detected on the bus
+                pub fn is_break_detected(&self) -> bool {
+                    self.serial.uart.s2.read().lbkdif().bit_is_set()
+                }
+
+                /// Clears the break detection flag
+                // Reference: 47.3.6 UART Status Register 2 (UART_S2)
+                pub fn clear_break(&mut self) {
+                    self.serial.uart.s2.modify(|_, w| w.lbkdif().set_bit().rxedgif().clear_bit());
+                }
+
+                /// Measures the sync byte following a break, adjusts the baud rate to it and
+                /// returns it
+                ///
+                /// Must be called before the start bit of the sync byte, the sync byte itself is
+                /// discarded
+                pub fn measure_sync(&mut self) -> Result<Bps<u32>, Error> {
+                    // The sync byte has 5 falling edges, 2 bit times apart
+                    // Reference: 47.3.6 UART Status Register 2 (UART_S2)
+                    let s2 = &self.serial.uart.s2;
+                    let mut start = 0;
+                    let mut end = 0;
+                    s2.modify(|_, w| w.rxedgif().set_bit().lbkdif().clear_bit());
+                    for edge in 0..5 {
+                        let timeout = if edge == 0 { HEADER_TIMEOUT } else { SYNC_EDGE_TIMEOUT };
+                        if !wait(self.clocks, timeout, || s2.read().rxedgif().bit_is_set()) {
+                            return Err(Error::Sync);
+                        }
+                        end = DWT::get_cycle_count();
+                        s2.modify(|_, w| w.rxedgif().set_bit().lbkdif().clear_bit());
+
+                        if edge == 0 {
+                            start = end;
+                        }
+                    }
+
+                    let cycles = u64::from(end.wrapping_sub(start));
+                    if cycles == 0 {
+                        return Err(Error::Sync);
+                    }
+
+                    let baud_rate = Bps((u64::from(self.clocks.core().0) * 8 / cycles) as u32);
+                    self.serial.set_baud_rate(baud_rate, self.clocks).map_err(|_| Error::Sync)?;
+
+                    // The sync byte is received, likely with errors, while the baud rate changes
+                    let rx = &mut self.rx;
+                    let received = wait(self.clocks, SYNC_EDGE_TIMEOUT, || match rx.read_word() {
+                        Err(nb::Error::WouldBlock) => false,
+                        _ => true,
+                    });
+                    if !received {
+                        return Err(Error::Sync);
+                    }
+
+                    Ok(baud_rate)
+                }
+
+                /// Sends a frame header as the master: a break, the sync byte and the protected
+                /// identifier
+                pub fn write_header(&mut self, id: u8) -> Result<(), Error> {
+                    let protected_id = protected_id(id);
+
+                    self.send_break();
+                    self.wait_for_break()?;
+
+                    let result = self.write_byte(SYNC).and_then(|_| self.write_byte(protected_id));
+                    self.end_frame_on_error(result)
+                }
+
+                /// Waits for a frame header as a slave, adjusts the baud rate to its sync byte and
+                /// returns its frame identifier
+                ///
+                /// Returns `Error::Timeout` if no break is detected within a frame header time at
+                /// the lowest baud rate, it can then be called again
+                pub fn read_header(&mut self) -> Result<u8, Error> {
+                    self.wait_for_break()?;
+
+                    let result = self.measure_sync().and_then(|_| {
+                        let protected_id = self.read_byte()?;
+                        frame_id(protected_id).ok_or(Error::ProtectedId)
+                    });
+                    self.end_frame_on_error(result)
+                }
+
+                /// Sends the response of a frame, the data bytes followed by the checksum, and
+                /// waits for the next frame
+                pub fn write_response(&mut self, id: u8, data: &[u8], model: Checksum) -> Result<(), Error> {
+                    check_response_len(data.len());
+
+                    let result = self.write_response_bytes(id, data, model);
+                    self.skip_response();
+                    result
+                }
+
+                /// Receives the response of a frame into `buffer`, checks its checksum, and waits
+                /// for the next frame
+                pub fn read_response(&mut self, id: u8, buffer: &mut [u8], model: Checksum) -> Result<(), Error> {
+                    check_response_len(buffer.len());
+
+                    let result = self.read_response_bytes(id, buffer, model);
+                    self.skip_response();
+                    result
+                }
+
+                /// Waits for the next frame without writing or reading the response of the
+                /// current frame, whose bytes are ignored
+                pub fn skip_response(&mut self) {
+                    self.set_break_detection(true);
+                }
+
+                fn write_response_bytes(&mut self, id: u8, data: &[u8], model: Checksum) -> Result<(), Error> {
+                    for &byte in data {
+                        self.write_byte(byte)?;
+                    }
+                    self.write_byte(checksum(model, protected_id(id), data))
+                }
+
+                fn read_response_bytes(&mut self, id: u8, buffer: &mut [u8], model: Checksum) -> Result<(), Error> {
+                    for byte in buffer.iter_mut() {
+                        *byte = self.read_byte()?;
+                    }
+
+                    if self.read_byte()? != checksum(model, protected_id(id), buffer) {
+                        return Err(Error::Checksum);
+                    }
+                    Ok(())
+                }
+
+                // The bytes of the frame can only be received once break detection is disabled
+                fn wait_for_break(&mut self) -> Result<(), Error> {
+                    if !wait(self.clocks, HEADER_TIMEOUT, || self.is_break_detected()) {
+                        return Err(Error::Timeout);
+                    }
+                    self.clear_break();
+                    self.set_break_detection(false);
+                    Ok(())
+                }
+
+                // A header that failed is not followed by a response, the next frame is awaited
+                fn end_frame_on_error<T>(&mut self, result: Result<T, Error>) -> Result<T, Error> {
+                    if result.is_err() {
+                        self.skip_response();
+                    }
+                    result
+                }
+
+                fn write_byte(&mut self, byte: u8) -> Result<(), Error> {
+                    nb::block!(Write::<u8>::write(&mut Tx::<$UARTX> { _uart: PhantomData }, byte))?;
+
+                    if self.read_byte()? != byte {
+                        return Err(Error::Bit);
+                    }
+                    Ok(())
+                }
+
+                fn read_byte(&mut self) -> Result<u8, Error> {
+                    Ok(nb::block!(Read::<u8>::read(&mut self.rx))?)
+                }
+
+                // Breaks are 13 bit times long, the only break length allowed by LIN. The break
+                // length can only be changed while the transmitter is disabled
+                // Reference: 47.3.6 UART Status Register 2 (UART_S2)
+                fn set_break(&mut self, lin: bool) {
+                    let uart = &self.serial.uart;
+                    while uart.s1.read().tc().bit_is_clear() {}
+
+                    interrupt::free(|_| uart.c2.modify(|_, w| w.te().clear_bit()));
+                    uart.s2.modify(|_, w| w.brk13().bit(lin).rxedgif().clear_bit().lbkdif().clear_bit());
+                    interrupt::free(|_| uart.c2.modify(|_, w| w.te().set_bit()));
+                }
+
+                // Breaks are detected instead of being received as a framing error, but no flag of
+                // S1 is set while break detection is enabled, so no byte can be received
+                // Reference: 47.3.6 UART Status Register 2 (UART_S2)
+                fn set_break_detection(&mut self, enable: bool) {
+                    self.serial.uart.s2.modify(|_, w| {
+                        w.lbkde().bit(enable);
+                        w.rxedgif().clear_bit();
+                        w.lbkdif().set_bit()
+                    });
+                }
+            }
+        )+
+    }
+}
+
+lin! {
+    UART0,
+    UART1,
+    UART2,
+    UART3,
+    UART4,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{checksum, frame_id, protected_id, Checksum};
+
+    #[test]
+    fn protected_ids() {
+        assert_eq!(protected_id(0x00), 0x80);
+        assert_eq!(protected_id(0x01), 0xC1);
+        assert_eq!(protected_id(0x10), 0x50);
+        assert_eq!(protected_id(0x3C), 0x3C);
+        assert_eq!(protected_id(0x3D), 0x7D);
+        assert_eq!(protected_id(0x3F), 0xBF);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_invalid_frame_id() {
+        protected_id(0x40);
+    }
+
+    #[test]
+    fn frame_ids() {
+        for id in 0..0x40 {
+            assert_eq!(frame_id(protected_id(id)), Some(id));
+        }
+
+        // Flipped parity bits
+        assert_eq!(frame_id(0x80 ^ 0x40), None);
+        assert_eq!(frame_id(0xC1 ^ 0x80), None);
+    }
+
+    #[test]
+    fn classic_checksums() {
+        assert_eq!(checksum(Checksum::Classic, 0x3C, &[0x01, 0x02]), 0xFC);
+
+        // 0xFF + 0x01 carries into 0x01
+        assert_eq!(checksum(Checksum::Classic, 0x3C, &[0xFF, 0x01]), 0xFE);
+        assert_eq!(checksum(Checksum::Classic, 0x3C, &[0xFF; 8]), 0x00);
+    }
+
+    #[test]
+    fn enhanced_checksums() {
+        // Example of the LIN 2.x specification
+        assert_eq!(checksum(Checksum::Enhanced, 0x4A, &[0x55, 0x93, 0xE5]), 0xE6);
+
+        assert_eq!(checksum(Checksum::Enhanced, 0x80, &[0x00]), 0x7F);
+    }
+}
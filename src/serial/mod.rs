@@ -0,0 +1,1336 @@
+use core::marker::PhantomData;
+
+use cortex_m::interrupt;
+use nb;
+use bitrate::{Bps, Hertz, U32BitrateExt};
+use bit_field::BitField;
+use hal::serial;
+use mk20d7::{self, UART0, UART1, UART2, UART3, UART4};
+
+use sealed::Sealed;
+use sim::Clocks;
+use gpio::{
+    Alternate, Disable, ALT2, ALT3,
+    gpioa::{PTA0, PTA1, PTA2, PTA3},
+    gpiob::{PTB2, PTB3, PTB16, PTB17},
+    gpioc::{PTC1, PTC2, PTC3, PTC4},
+    gpiod::{PTD0, PTD1, PTD2, PTD3, PTD4, PTD5, PTD6, PTD7},
+    gpioe::{PTE0, PTE1},
+};
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+use gpio::{
+    gpioa::{PTA14, PTA15, PTA16, PTA17},
+    gpiob::{PTB10, PTB11},
+    gpioc::{PTC12, PTC13, PTC14, PTC15, PTC16, PTC17, PTC18, PTC19},
+    gpioe::{PTE2, PTE3, PTE4, PTE5, PTE6, PTE7, PTE24, PTE25, PTE26},
+};
+
+mod buffered;
+mod dma;
+pub mod iso7816;
+pub mod lin;
+mod rs485;
+
+pub use self::buffered::BufferedSerial;
+pub use self::dma::CircularRx;
+pub use self::rs485::{Rs485, Rs485Serial};
+
+/// Interrupt event
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    /// New data has been received
+    Rxne,
+
+    /// New data can be sent
+    Txe,
+
+    /// Transmission complete
+    Tc,
+
+    /// The receive line became idle
+    Idle,
+
+    /// Overrun error
+    Overrun,
+
+    /// Noise error
+    Noise,
+
+    /// Framing error
+    Framing,
+
+    /// Parity error
+    Parity,
+}
+
+/// Serial error
+#[derive(Debug)]
+pub enum Error {
+    /// Framing error
+    Framing,
+
+    /// Noise error
+    Noise,
+
+    /// RX buffer overrun
+    Overrun,
+
+    /// Parity check error
+    Parity,
+
+    /// Word written to a full TX FIFO
+    FifoOverflow,
+
+    /// Word read from an empty RX FIFO
+    FifoUnderflow,
+
+    #[doc(hidden)]
+    _Extensible,
+}
+
+/// Receive error flags raised together for a word
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ErrorFlags {
+    /// Framing error
+    pub framing: bool,
+
+    /// Noise error
+    pub noise: bool,
+
+    /// RX buffer overrun
+    pub overrun: bool,
+
+    /// Parity check error
+    pub parity: bool,
+
+    /// Word read from an empty RX FIFO
+    pub fifo_underflow: bool,
+}
+
+impl ErrorFlags {
+    /// Returns the most severe of the raised errors, if any
+    pub fn error(&self) -> Option<Error> {
+        if self.parity {
+            Some(Error::Parity)
+        } else if self.framing {
+            Some(Error::Framing)
+        } else if self.noise {
+            Some(Error::Noise)
+        } else if self.overrun {
+            Some(Error::Overrun)
+        } else if self.fifo_underflow {
+            Some(Error::FifoUnderflow)
+        } else {
+            None
+        }
+    }
+}
+
+/// Received word
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Word {
+    /// Data bits, the ninth bit included
+    pub data: u16,
+
+    /// Noise was detected while sampling the word, the data may still be valid
+    pub noise: bool,
+
+    /// The words received after this one were lost to an overrun, this word is still valid
+    pub overrun: bool,
+}
+
+/// Parity bit
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Parity {
+    /// No parity bit
+    None,
+
+    /// Even parity bit
+    Even,
+
+    /// Odd parity bit
+    Odd,
+}
+
+/// Direction of the TX pin in single wire mode
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// The TX pin is an input, driven by the other devices
+    Receive,
+
+    /// The TX pin is an output, driven by the transmitter
+    Transmit,
+}
+
+/// Condition waking up a sleeping receiver on a multi-drop bus
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Wakeup {
+    /// The receive line becomes idle
+    IdleLine,
+
+    /// An address word is received, with its most significant bit set
+    AddressMark,
+}
+
+/// Number of data bits in a frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DataBits {
+    /// 8 data bits, read and written as `u8`
+    Eight,
+
+    /// 9 data bits, read and written as `u16`
+    Nine,
+}
+
+/// Number of stop bits in a frame
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopBits {
+    /// 1 stop bit
+    One,
+
+    /// 2 stop bits, only with 8 data bits and no parity bit
+    ///
+    /// The UART has no stop bit setting, so the frames are sent and received with a ninth data
+    /// bit that is always high instead. The receiver samples that bit as data, so the peer must
+    /// send at least 2 stop bits: the start bit of a frame sent right after 1 stop bit is taken
+    /// for the ninth bit, which loses the synchronization
+    Two,
+}
+
+/// Serial configuration
+#[derive(Clone, Copy, Debug)]
+pub struct Config {
+    /// Baud rate
+    pub baud_rate: Bps<u32>,
+
+    /// Parity bit
+    pub parity: Parity,
+
+    /// Number of data bits
+    pub data_bits: DataBits,
+
+    /// Number of stop bits
+    pub stop_bits: StopBits,
+
+    /// Sends and receives the most significant bit first
+    pub msb_first: bool,
+
+    /// Inverts the received data
+    pub invert_rx: bool,
+
+    /// Inverts the transmitted data
+    pub invert_tx: bool,
+}
+
+impl Default for Config {
+    /// 115200 baud, 8 data bits, no parity bit, 1 stop bit (8N1)
+    fn default() -> Self {
+        Config {
+            baud_rate: 115_200.bps(),
+            parity: Parity::None,
+            data_bits: DataBits::Eight,
+            stop_bits: StopBits::One,
+            msb_first: false,
+            invert_rx: false,
+            invert_tx: false,
+        }
+    }
+}
+
+/// Largest difference between the requested and the achieved baud rate, in percent
+pub const BAUD_RATE_TOLERANCE: f32 = 3.0;
+
+/// Baud rate that cannot be generated from the module clock within the tolerance
+#[derive(Debug)]
+pub struct BaudRateError {
+    /// Closest baud rate that can be generated
+    pub baud_rate: Bps<u32>,
+
+    /// Difference between the closest and the requested baud rate, in percent
+    pub error: f32,
+}
+
+/// Serial configuration that cannot be applied
+#[derive(Debug)]
+pub enum ConfigError {
+    /// The baud rate cannot be generated from the module clock
+    BaudRate(BaudRateError),
+
+    /// 2 stop bits require 8 data bits and no parity bit
+    StopBits,
+}
+
+impl From<BaudRateError> for ConfigError {
+    fn from(error: BaudRateError) -> Self {
+        ConfigError::BaudRate(error)
+    }
+}
+
+// Number of words of a FIFO from its size field, the reserved size is taken as no FIFO
+// Reference: 47.3.16 UART FIFO Parameters (UART_PFIFO)
+fn fifo_size(size: u8) -> u8 {
+    match size {
+        0b001 => 4,
+        0b010 => 8,
+        0b011 => 16,
+        0b100 => 32,
+        0b101 => 64,
+        0b110 => 128,
+        _ => 1,
+    }
+}
+
+// Reference: 47.3.1 UART Baud Rate Registers: High (UART_BDH)
+const MODULE_CLOCK_DIVISOR_MAX: u32 = 0x1FFF;
+
+// Returns the module clock divisor (SBR) and the fine adjustment (BRFA) closest to `baud_rate`
+// Reference: 47.4.4 Baud rate generation
+fn baud_rate_divisors(clock: Hertz<u32>, baud_rate: Bps<u32>) -> Result<(u16, u8), BaudRateError> {
+    // baud rate = clock / (16 * (SBR + BRFA / 32)), so the divisor in 32nds of a unit is
+    // 2 * clock / baud rate
+    let clock = u64::from(clock.0);
+    let requested = u64::from(baud_rate.0);
+    let divisor = (2 * clock + requested / 2) / requested;
+
+    let minimum = 32;
+    let maximum = u64::from(MODULE_CLOCK_DIVISOR_MAX) * 32 + 31;
+    let divisor = if divisor < minimum {
+        minimum
+    } else if divisor > maximum {
+        maximum
+    } else {
+        divisor
+    };
+
+    let achieved = (2 * clock + divisor / 2) / divisor;
+    let error = (achieved as f32 - requested as f32) * 100.0 / requested as f32;
+
+    if !(-BAUD_RATE_TOLERANCE..=BAUD_RATE_TOLERANCE).contains(&error) {
+        return Err(BaudRateError { baud_rate: Bps(achieved as u32), error });
+    }
+
+    Ok(((divisor >> 5) as u16, (divisor & 0b1_1111) as u8))
+}
+
+/// TX pin
+///
+/// ```
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::gpio::{Alternate, ALT3, gpiod::PTD3};
+/// # use mk20d7_hal::mk20d7::UART2;
+/// # use mk20d7_hal::serial::TxPin;
+/// fn tx<PIN: TxPin<UART2>>() {}
+/// tx::<PTD3<Alternate<ALT3>>>();
+/// ```
+///
+/// A pin of another UART is rejected
+///
+/// ```compile_fail,E0277
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::gpio::{Alternate, ALT3, gpiod::PTD3};
+/// # use mk20d7_hal::mk20d7::UART1;
+/// # use mk20d7_hal::serial::TxPin;
+/// fn tx<PIN: TxPin<UART1>>() {}
+/// tx::<PTD3<Alternate<ALT3>>>();
+/// ```
+///
+/// An RX pin is rejected
+///
+/// ```compile_fail,E0277
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::gpio::{Alternate, ALT3, gpiob::PTB16};
+/// # use mk20d7_hal::mk20d7::UART0;
+/// # use mk20d7_hal::serial::TxPin;
+/// fn tx<PIN: TxPin<UART0>>() {}
+/// tx::<PTB16<Alternate<ALT3>>>();
+/// ```
+pub trait TxPin<UART>: Sealed {}
+
+/// RX pin
+///
+/// ```
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::gpio::{Alternate, ALT2, gpioa::PTA1};
+/// # use mk20d7_hal::mk20d7::UART0;
+/// # use mk20d7_hal::serial::RxPin;
+/// fn rx<PIN: RxPin<UART0>>() {}
+/// rx::<PTA1<Alternate<ALT2>>>();
+/// ```
+///
+/// A pin in the wrong alternate function is rejected
+///
+/// ```compile_fail,E0277
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::gpio::{Alternate, ALT3, gpioa::PTA1};
+/// # use mk20d7_hal::mk20d7::UART0;
+/// # use mk20d7_hal::serial::RxPin;
+/// fn rx<PIN: RxPin<UART0>>() {}
+/// rx::<PTA1<Alternate<ALT3>>>();
+/// ```
+pub trait RxPin<UART>: Sealed {}
+
+/// CTS (clear to send) pin
+///
+/// ```
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::gpio::{Alternate, ALT3, gpiod::PTD1};
+/// # use mk20d7_hal::mk20d7::UART2;
+/// # use mk20d7_hal::serial::CtsPin;
+/// fn cts<PIN: CtsPin<UART2>>() {}
+/// cts::<PTD1<Alternate<ALT3>>>();
+/// ```
+///
+/// An RTS pin is rejected
+///
+/// ```compile_fail,E0277
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::gpio::{Alternate, ALT3, gpiod::PTD0};
+/// # use mk20d7_hal::mk20d7::UART2;
+/// # use mk20d7_hal::serial::CtsPin;
+/// fn cts<PIN: CtsPin<UART2>>() {}
+/// cts::<PTD0<Alternate<ALT3>>>();
+/// ```
+pub trait CtsPin<UART>: Sealed {}
+
+/// RTS (request to send) pin
+///
+/// ```
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::gpio::{Alternate, ALT3, gpioc::PTC1};
+/// # use mk20d7_hal::mk20d7::UART1;
+/// # use mk20d7_hal::serial::RtsPin;
+/// fn rts<PIN: RtsPin<UART1>>() {}
+/// rts::<PTC1<Alternate<ALT3>>>();
+/// ```
+///
+/// A pin of another UART is rejected
+///
+/// ```compile_fail,E0277
+/// # extern crate mk20d7_hal;
+/// # use mk20d7_hal::gpio::{Alternate, ALT3, gpioc::PTC1};
+/// # use mk20d7_hal::mk20d7::UART0;
+/// # use mk20d7_hal::serial::RtsPin;
+/// fn rts<PIN: RtsPin<UART0>>() {}
+/// rts::<PTC1<Alternate<ALT3>>>();
+/// ```
+pub trait RtsPin<UART>: Sealed {}
+
+// Reference: 10.3.1 K20 Signal Multiplexing and Pin Assignments
+
+// UART 0 PTA
+impl CtsPin<UART0> for PTA0<Alternate<ALT2>> {}
+impl RxPin<UART0> for PTA1<Alternate<ALT2>> {}
+impl TxPin<UART0> for PTA2<Alternate<ALT2>> {}
+impl RtsPin<UART0> for PTA3<Alternate<ALT2>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl TxPin<UART0> for PTA14<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RxPin<UART0> for PTA15<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl CtsPin<UART0> for PTA16<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RtsPin<UART0> for PTA17<Alternate<ALT3>> {}
+
+// UART 0 PTB
+impl RtsPin<UART0> for PTB2<Alternate<ALT3>> {}
+impl CtsPin<UART0> for PTB3<Alternate<ALT3>> {}
+impl RxPin<UART0> for PTB16<Alternate<ALT3>> {}
+impl TxPin<UART0> for PTB17<Alternate<ALT3>> {}
+
+// UART 0 PTD
+impl RtsPin<UART0> for PTD4<Alternate<ALT3>> {}
+impl CtsPin<UART0> for PTD5<Alternate<ALT3>> {}
+impl RxPin<UART0> for PTD6<Alternate<ALT3>> {}
+impl TxPin<UART0> for PTD7<Alternate<ALT3>> {}
+
+// UART 1 PTC
+impl RtsPin<UART1> for PTC1<Alternate<ALT3>> {}
+impl CtsPin<UART1> for PTC2<Alternate<ALT3>> {}
+impl RxPin<UART1> for PTC3<Alternate<ALT3>> {}
+impl TxPin<UART1> for PTC4<Alternate<ALT3>> {}
+
+// UART 1 PTE
+impl TxPin<UART1> for PTE0<Alternate<ALT3>> {}
+impl RxPin<UART1> for PTE1<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl CtsPin<UART1> for PTE2<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RtsPin<UART1> for PTE3<Alternate<ALT3>> {}
+
+// UART 2 PTD
+impl RtsPin<UART2> for PTD0<Alternate<ALT3>> {}
+impl CtsPin<UART2> for PTD1<Alternate<ALT3>> {}
+impl RxPin<UART2> for PTD2<Alternate<ALT3>> {}
+impl TxPin<UART2> for PTD3<Alternate<ALT3>> {}
+
+// UART 3 PTB
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RxPin<UART3> for PTB10<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl TxPin<UART3> for PTB11<Alternate<ALT3>> {}
+
+// UART 3 PTC
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RxPin<UART3> for PTC16<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl TxPin<UART3> for PTC17<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RtsPin<UART3> for PTC18<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl CtsPin<UART3> for PTC19<Alternate<ALT3>> {}
+
+// UART 3 PTE
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl TxPin<UART3> for PTE4<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RxPin<UART3> for PTE5<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl CtsPin<UART3> for PTE6<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RtsPin<UART3> for PTE7<Alternate<ALT3>> {}
+
+// UART 4 PTC
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RtsPin<UART4> for PTC12<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl CtsPin<UART4> for PTC13<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RxPin<UART4> for PTC14<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl TxPin<UART4> for PTC15<Alternate<ALT3>> {}
+
+// UART 4 PTE
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl TxPin<UART4> for PTE24<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl RxPin<UART4> for PTE25<Alternate<ALT3>> {}
+#[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+impl CtsPin<UART4> for PTE26<Alternate<ALT3>> {}
+
+/// Serial abstraction
+pub struct Serial<UART, PINS> {
+    uart: UART,
+    pins: PINS,
+}
+
+/// Serial receiver
+pub struct Rx<UART> {
+    _uart: PhantomData<UART>,
+    // Error of the last word read, reported by the next read
+    error: Option<Error>,
+}
+
+/// Serial transmitter
+pub struct Tx<UART> {
+    _uart: PhantomData<UART>,
+}
+
+macro_rules! hal {
+    ($(
+        $UARTX:ident: ($uartX:ident, $uartX_half_duplex:ident, $clock:ident),
+    )+) => {
+        $(
+            impl<PINS> Serial<$UARTX, PINS> {
+                // Configures the frame and the baud rate, then enables the transmitter and the
+                // receiver
+                // Reference 47.8.3 Initialization sequence (non ISO-7816)
+                fn configure(
+                    uart: &$UARTX,
+                    config: Config,
+                    clocks: Clocks,
+                    loop_mode: bool,
+                    single_wire: bool,
+                ) -> Result<(), ConfigError> {
+                    let (module_clock_divisor, module_clock_divisor_fine_adjustment) = {
+                        baud_rate_divisors(clocks.$clock(), config.baud_rate)?
+                    };
+
+                    let (nine_bit, ten_bit) = match (config.data_bits, config.parity, config.stop_bits) {
+                        (DataBits::Eight, Parity::None, StopBits::One) => (false, false),
+                        (DataBits::Eight, Parity::None, StopBits::Two) => (true, false),
+                        (DataBits::Eight, _, StopBits::One) => (true, false),
+                        (DataBits::Nine, Parity::None, StopBits::One) => (true, false),
+                        (DataBits::Nine, _, StopBits::One) => (true, true),
+                        (_, _, StopBits::Two) => return Err(ConfigError::StopBits),
+                    };
+
+                    // The receiver is connected to the transmitter in loop mode, or to the TX pin in
+                    // single wire mode
+                    // Reference: 47.3.3 UART Control Register 1 (UART_C1)
+                    uart.c1.write(|w| {
+                        w.loops().bit(loop_mode || single_wire);
+                        w.rsrc().bit(single_wire);
+                        w.m().bit(nine_bit);
+                        w.pe().bit(config.parity != Parity::None);
+                        w.pt().bit(config.parity == Parity::Odd)
+                    });
+
+                    // Reference: 47.3.11 UART Control Register 4 (UART_C4)
+                    uart.c4.write(|w| unsafe {
+                        w.m10().bit(ten_bit);
+                        w.brfa().bits(module_clock_divisor_fine_adjustment)
+                    });
+
+                    // The ninth data bit is the second stop bit
+                    // Reference: 47.3.7 UART Control Register 3 (UART_C3)
+                    uart.c3.write(|w| {
+                        w.txinv().bit(config.invert_tx);
+                        w.t8().bit(config.stop_bits == StopBits::Two)
+                    });
+
+                    // Reference: 47.3.6 UART Status Register 2 (UART_S2)
+                    uart.s2.write(|w| {
+                        w.msbf().bit(config.msb_first);
+                        w.rxinv().bit(config.invert_rx)
+                    });
+
+                    Self::write_module_clock_divisor(uart, module_clock_divisor);
+
+                    // Reference: 47.3.4 UART Control Register 2 (UART_C2)
+                    uart.c2.write(|w| {
+                        w.re().set_bit();
+                        w.te().set_bit()
+                    });
+
+                    Ok(())
+                }
+
+                /// Changes the baud rate, the frame being received or transmitted is corrupted
+                pub fn set_baud_rate(
+                    &mut self,
+                    baud_rate: Bps<u32>,
+                    clocks: Clocks,
+                ) -> Result<(), BaudRateError> {
+                    let (module_clock_divisor, module_clock_divisor_fine_adjustment) = {
+                        baud_rate_divisors(clocks.$clock(), baud_rate)?
+                    };
+
+                    // Reference: 47.3.11 UART Control Register 4 (UART_C4)
+                    self.uart.c4.modify(|_, w| unsafe {
+                        w.brfa().bits(module_clock_divisor_fine_adjustment)
+                    });
+                    Self::write_module_clock_divisor(&self.uart, module_clock_divisor);
+
+                    Ok(())
+                }
+
+                // The divisor is only updated once the low register is written
+                // Reference: 47.3.1 UART Baud Rate Registers: High (UART_BDH)
+                // Reference: 47.3.2 UART Baud Rate Registers: Low (UART_BDL)
+                fn write_module_clock_divisor(uart: &$UARTX, module_clock_divisor: u16) {
+                    let module_clock_divisor_high = module_clock_divisor.get_bits(8..13) as u8;
+                    uart.bdh.modify(|_, w| unsafe { w.sbr().bits(module_clock_divisor_high) });
+
+                    let module_clock_divisor_low = module_clock_divisor.get_bits(0..8) as u8;
+                    uart.bdl.write(|w| unsafe { w.sbr().bits(module_clock_divisor_low) });
+                }
+            }
+
+            impl<TX, RX> Serial<$UARTX, (TX, RX)> {
+                /// Configures a UART peripheral to provide serial communication
+                pub fn $uartX(
+                    uart: $UARTX,
+                    pins: (TX, RX),
+                    config: Config,
+                    clocks: Clocks,
+                ) -> Result<Self, ConfigError>
+                where
+                    TX: TxPin<$UARTX>,
+                    RX: RxPin<$UARTX>,
+                {
+                    Self::configure(&uart, config, clocks, false, false)?;
+
+                    Ok(Serial { uart, pins })
+                }
+
+                /// Connects the transmitter output to the receiver input internally, the RX pin is
+                /// not used while enabled
+                pub fn enable_loopback(&mut self) {
+                    self.set_loopback(true);
+                }
+
+                /// Connects the receiver input back to the RX pin
+                pub fn disable_loopback(&mut self) {
+                    self.set_loopback(false);
+                }
+
+                // Reference: 47.3.3 UART Control Register 1 (UART_C1)
+                fn set_loopback(&mut self, enable: bool) {
+                    self.uart.c1.modify(|_, w| {
+                        w.loops().bit(enable);
+                        w.rsrc().clear_bit()
+                    });
+                }
+
+                /// Waits for the transmission to complete, disables the UART peripheral, and
+                /// releases it with the associated pins returned to their disabled state
+                pub fn release(self) -> ($UARTX, (TX::Disabled, RX::Disabled))
+                where
+                    TX: Disable,
+                    RX: Disable,
+                {
+                    while self.uart.s1.read().tc().bit_is_clear() {}
+
+                    // Reference: 47.3.4 UART Control Register 2 (UART_C2)
+                    self.uart.c2.modify(|_, w| w.re().clear_bit().te().clear_bit());
+
+                    let (tx, rx) = self.pins;
+                    (self.uart, (tx.disable(), rx.disable()))
+                }
+
+                /// Enables hardware flow control
+                ///
+                /// The transmitter holds each word until CTS is asserted, so writes report
+                /// `WouldBlock` once the transmit buffer is full; the receiver deasserts RTS while
+                /// its buffer is full
+                pub fn enable_flow_control<CTS, RTS>(
+                    self,
+                    cts: CTS,
+                    rts: RTS,
+                ) -> Serial<$UARTX, (TX, RX, CTS, RTS)>
+                where
+                    CTS: CtsPin<$UARTX>,
+                    RTS: RtsPin<$UARTX>,
+                {
+                    // Reference: 47.3.14 UART Modem Register (UART_MODEM)
+                    self.uart.modem.modify(|_, w| {
+                        w.txctse().set_bit();
+                        w.rxrtse().set_bit()
+                    });
+
+                    let (tx, rx) = self.pins;
+                    Serial { uart: self.uart, pins: (tx, rx, cts, rts) }
+                }
+            }
+
+            impl<TX, RX, CTS, RTS> Serial<$UARTX, (TX, RX, CTS, RTS)> {
+                /// Disables hardware flow control, and releases the CTS and RTS pins
+                pub fn disable_flow_control(self) -> (Serial<$UARTX, (TX, RX)>, CTS, RTS) {
+                    // Reference: 47.3.14 UART Modem Register (UART_MODEM)
+                    self.uart.modem.modify(|_, w| {
+                        w.txctse().clear_bit();
+                        w.rxrtse().clear_bit()
+                    });
+
+                    let (tx, rx, cts, rts) = self.pins;
+                    (Serial { uart: self.uart, pins: (tx, rx) }, cts, rts)
+                }
+            }
+
+            impl<TX> Serial<$UARTX, TX>
+            where
+                TX: TxPin<$UARTX>,
+            {
+                /// Configures a UART peripheral to provide single wire half-duplex serial
+                /// communication over the TX pin, which starts in the receive direction
+                ///
+                /// The TX pin is usually configured as open drain, with a pull-up resistor
+                pub fn $uartX_half_duplex(
+                    uart: $UARTX,
+                    pin: TX,
+                    config: Config,
+                    clocks: Clocks,
+                ) -> Result<Self, ConfigError> {
+                    Self::configure(&uart, config, clocks, false, true)?;
+
+                    Ok(Serial { uart, pins: pin })
+                }
+
+                /// Sets the direction of the TX pin, the direction should only go back to
+                /// `Receive` once the transmission is complete
+                pub fn set_direction(&mut self, direction: Direction) {
+                    Tx::<$UARTX> { _uart: PhantomData }.set_direction(direction);
+                }
+            }
+
+            impl<PINS> Serial<$UARTX, PINS> {
+                /// Splits the `Serial` abstraction into a transmitter and a receiver half
+                pub fn split(self) -> (Tx<$UARTX>, Rx<$UARTX>) {
+                    (Tx { _uart: PhantomData }, Rx { _uart: PhantomData, error: None })
+                }
+
+                /// Releases the UART peripheral and associated pins
+                pub fn free(self) -> ($UARTX, PINS) {
+                    (self.uart, self.pins)
+                }
+            }
+
+            fn $uartX<'a>() -> &'a mk20d7::$uartX::RegisterBlock {
+                unsafe { &(*$UARTX::ptr()) }
+            }
+
+            impl Rx<$UARTX> {
+                // Checks for a FIFO underflow and for data in the receive buffer or FIFO
+                fn ready(&self) -> nb::Result<(), Error> {
+                    let uart = $uartX();
+
+                    // Reference: 47.3.18 UART FIFO Status Register (UART_SFIFO)
+                    if uart.sfifo.read().rxuf().bit_is_set() {
+                        uart.sfifo.write(|w| w.rxuf().set_bit());
+                        return Err(nb::Error::Other(Error::FifoUnderflow));
+                    }
+
+                    if uart.s1.read().rdrf().bit_is_set() {
+                        return Ok(());
+                    }
+
+                    // The receive data register full flag only tracks the watermark, the FIFO may
+                    // hold fewer words
+                    // Reference: 47.3.22 UART FIFO Receive Count (UART_RCFIFO)
+                    if uart.pfifo.read().rxfe().bit_is_set() && uart.rcfifo.read().rxcount().bits() != 0 {
+                        return Ok(());
+                    }
+
+                    Err(nb::Error::WouldBlock)
+                }
+            }
+
+            impl Tx<$UARTX> {
+                // Checks for a FIFO overflow and for room in the transmit buffer or FIFO
+                fn ready(&self) -> nb::Result<(), Error> {
+                    let uart = $uartX();
+
+                    // Reference: 47.3.18 UART FIFO Status Register (UART_SFIFO)
+                    if uart.sfifo.read().txof().bit_is_set() {
+                        uart.sfifo.write(|w| w.txof().set_bit());
+                        return Err(nb::Error::Other(Error::FifoOverflow));
+                    }
+
+                    if uart.s1.read().tdre().bit_is_set() {
+                        return Ok(());
+                    }
+
+                    // The transmit data register empty flag only tracks the watermark, the FIFO may
+                    // still have room
+                    // Reference: 47.3.20 UART FIFO Transmit Count (UART_TCFIFO)
+                    let pfifo = uart.pfifo.read();
+                    let size = fifo_size(pfifo.txfifosize().bits());
+                    if pfifo.txfe().bit_is_set() && uart.tcfifo.read().txcount().bits() < size {
+                        return Ok(());
+                    }
+
+                    Err(nb::Error::WouldBlock)
+                }
+            }
+
+            impl<PINS> Serial<$UARTX, PINS> {
+                /// Starts listening for an interrupt event
+                pub fn listen(&mut self, event: Event) {
+                    self.set_event(event, true);
+                }
+
+                /// Stops listening for an interrupt event
+                pub fn unlisten(&mut self, event: Event) {
+                    self.set_event(event, false);
+                }
+
+                fn set_event(&mut self, event: Event, enable: bool) {
+                    let mut tx = Tx::<$UARTX> { _uart: PhantomData };
+                    let mut rx = Rx::<$UARTX> { _uart: PhantomData, error: None };
+                    match event {
+                        Event::Txe | Event::Tc => tx.set_event(event, enable),
+                        _ => rx.set_event(event, enable),
+                    }
+                }
+            }
+
+            impl Tx<$UARTX> {
+                /// Starts listening for a transmitter interrupt event, `Txe` or `Tc`
+                ///
+                /// Receiver events are ignored, and rejected by debug builds
+                pub fn listen(&mut self, event: Event) {
+                    self.set_event(event, true);
+                }
+
+                /// Stops listening for a transmitter interrupt event, `Txe` or `Tc`
+                pub fn unlisten(&mut self, event: Event) {
+                    self.set_event(event, false);
+                }
+
+                /// Sets the direction of the TX pin in single wire mode, the direction should only
+                /// go back to `Receive` once `flush` reports the transmission complete
+                // The receiver shares the control register, so it is modified atomically
+                // Reference: 47.3.7 UART Control Register 3 (UART_C3)
+                pub fn set_direction(&mut self, direction: Direction) {
+                    let uart = $uartX();
+                    interrupt::free(|_| {
+                        uart.c3.modify(|_, w| w.txdir().bit(direction == Direction::Transmit))
+                    });
+                }
+
+                // The receiver shares the control registers, so they are modified atomically
+                // Reference: 47.3.4 UART Control Register 2 (UART_C2)
+                fn set_event(&mut self, event: Event, enable: bool) {
+                    let uart = $uartX();
+                    interrupt::free(|_| match event {
+                        Event::Txe => uart.c2.modify(|_, w| w.tie().bit(enable)),
+                        Event::Tc => uart.c2.modify(|_, w| w.tcie().bit(enable)),
+                        _ => debug_assert!(false, "Invalid UART transmitter event: {:?}", event),
+                    });
+                }
+            }
+
+            impl Rx<$UARTX> {
+                /// Starts listening for a receiver interrupt event, any event but `Txe` and `Tc`
+                ///
+                /// Transmitter events are ignored, and rejected by debug builds
+                pub fn listen(&mut self, event: Event) {
+                    self.set_event(event, true);
+                }
+
+                /// Stops listening for a receiver interrupt event, any event but `Txe` and `Tc`
+                pub fn unlisten(&mut self, event: Event) {
+                    self.set_event(event, false);
+                }
+
+                /// Returns `true` if the receive line became idle since the flag was cleared
+                pub fn is_idle(&self) -> bool {
+                    $uartX().s1.read().idle().bit_is_set()
+                }
+
+                /// Clears the idle flag, discarding the word in the data register if any
+                // Reference: 47.3.5 UART Status Register 1 (UART_S1)
+                pub fn clear_idle(&mut self) {
+                    self.clear_status();
+                }
+
+                /// Clears the overrun, noise, framing and parity error flags, discarding the word
+                /// in the data register if any
+                pub fn clear_errors(&mut self) {
+                    self.clear_status();
+                }
+
+                /// Puts the receiver to sleep, received words are ignored until the wakeup
+                /// condition
+                ///
+                /// An address word with a match address enabled only wakes the receiver if the
+                /// address matches
+                // The transmitter shares the control registers, so they are modified atomically
+                // Reference: 47.3.3 UART Control Register 1 (UART_C1)
+                // Reference: 47.3.4 UART Control Register 2 (UART_C2)
+                pub fn sleep(&mut self, wakeup: Wakeup) {
+                    let uart = $uartX();
+                    interrupt::free(|_| {
+                        uart.c1.modify(|_, w| w.wake().bit(wakeup == Wakeup::AddressMark));
+                        uart.c2.modify(|_, w| w.rwu().set_bit());
+                    });
+                }
+
+                /// Wakes the receiver up
+                pub fn wake(&mut self) {
+                    let uart = $uartX();
+                    interrupt::free(|_| uart.c2.modify(|_, w| w.rwu().clear_bit()));
+                }
+
+                /// Returns `true` while the receiver is sleeping, the receiver wakes up by itself
+                pub fn is_sleeping(&self) -> bool {
+                    $uartX().c2.read().rwu().bit_is_set()
+                }
+
+                /// Only receives the words following an address word that matches one of the
+                /// addresses, in 9-bit mode; `None` disables a match address
+                ///
+                /// Address words that do not match are discarded, as are the data words that
+                /// follow them
+                // Reference: 47.3.9 UART Match Address Registers 1 (UART_MA1)
+                // Reference: 47.3.10 UART Match Address Registers 2 (UART_MA2)
+                // Reference: 47.3.11 UART Control Register 4 (UART_C4)
+                pub fn set_match_addresses(&mut self, address1: Option<u8>, address2: Option<u8>) {
+                    let uart = $uartX();
+
+                    if let Some(address) = address1 {
+                        uart.ma1.write(|w| unsafe { w.ma().bits(address) });
+                    }
+
+                    if let Some(address) = address2 {
+                        uart.ma2.write(|w| unsafe { w.ma().bits(address) });
+                    }
+
+                    uart.c4.modify(|_, w| {
+                        w.maen1().bit(address1.is_some());
+                        w.maen2().bit(address2.is_some())
+                    });
+                }
+
+                /// Reads a word along with its noise and overrun flags
+                ///
+                /// Framing and parity errors are reported together, and the word in the data
+                /// register is discarded to clear them. After an overrun the data register still
+                /// holds the last valid word, which is returned
+                // Reference: 47.3.5 UART Status Register 1 (UART_S1)
+                pub fn read_word(&mut self) -> nb::Result<Word, ErrorFlags> {
+                    let uart = $uartX();
+                    let s1 = uart.s1.read();
+                    let mut flags = ErrorFlags {
+                        framing: s1.fe().bit_is_set(),
+                        noise: s1.nf().bit_is_set(),
+                        overrun: s1.or().bit_is_set(),
+                        parity: s1.pf().bit_is_set(),
+                        fifo_underflow: false,
+                    };
+
+                    if flags.framing || flags.parity {
+                        self.clear_status();
+                        return Err(nb::Error::Other(flags));
+                    }
+
+                    match self.ready() {
+                        Ok(()) => {},
+                        Err(nb::Error::WouldBlock) => return Err(nb::Error::WouldBlock),
+                        Err(nb::Error::Other(_)) => {
+                            flags.fifo_underflow = true;
+                            return Err(nb::Error::Other(flags));
+                        },
+                    }
+
+                    // The ninth bit must be read before the data register, which clears the noise
+                    // flag as S1 was read first
+                    // Reference: 47.3.7 UART Control Register 3 (UART_C3)
+                    let r8 = uart.c3.read().r8().bit_is_set();
+                    let byte = uart.d.read().rt().bits();
+
+                    Ok(Word {
+                        data: (u16::from(r8) << 8) | u16::from(byte),
+                        noise: flags.noise,
+                        overrun: flags.overrun,
+                    })
+                }
+
+                // The idle and error flags are cleared by reading S1 and then D, the underflow
+                // raised by reading an empty FIFO is cleared as well
+                // Reference: 47.3.18 UART FIFO Status Register (UART_SFIFO)
+                fn clear_status(&mut self) {
+                    let uart = $uartX();
+                    let empty = uart.sfifo.read().rxempt().bit_is_set();
+                    uart.s1.read();
+                    uart.d.read();
+                    if empty {
+                        uart.sfifo.write(|w| w.rxuf().set_bit());
+                    }
+                }
+
+                // Clears the idle flag only if the same status read shows no word left to read,
+                // so a word received meanwhile is not discarded. Returns `false` if a word is left
+                // Reference: 47.3.5 UART Status Register 1 (UART_S1)
+                // Reference: 47.3.18 UART FIFO Status Register (UART_SFIFO)
+                fn clear_idle_if_empty(&mut self) -> bool {
+                    let uart = $uartX();
+                    interrupt::free(|_| {
+                        let s1 = uart.s1.read();
+                        if s1.rdrf().bit_is_set() || uart.sfifo.read().rxempt().bit_is_clear() {
+                            return false;
+                        }
+
+                        if s1.idle().bit_is_set() {
+                            uart.d.read();
+                            uart.sfifo.write(|w| w.rxuf().set_bit());
+                        }
+                        true
+                    })
+                }
+
+                // The transmitter shares the control registers, so they are modified atomically
+                // Reference: 47.3.4 UART Control Register 2 (UART_C2)
+                // Reference: 47.3.7 UART Control Register 3 (UART_C3)
+                fn set_event(&mut self, event: Event, enable: bool) {
+                    let uart = $uartX();
+                    interrupt::free(|_| match event {
+                        Event::Rxne => uart.c2.modify(|_, w| w.rie().bit(enable)),
+                        Event::Idle => uart.c2.modify(|_, w| w.ilie().bit(enable)),
+                        Event::Overrun => uart.c3.modify(|_, w| w.orie().bit(enable)),
+                        Event::Noise => uart.c3.modify(|_, w| w.neie().bit(enable)),
+                        Event::Framing => uart.c3.modify(|_, w| w.feie().bit(enable)),
+                        Event::Parity => uart.c3.modify(|_, w| w.peie().bit(enable)),
+                        _ => debug_assert!(false, "Invalid UART receiver event: {:?}", event),
+                    });
+                }
+            }
+
+            impl serial::Read<u8> for Rx<$UARTX> {
+                type Error = Error;
+
+                fn read(&mut self) -> nb::Result<u8, Error> {
+                    serial::Read::<u16>::read(self).map(|word| word as u8)
+                }
+            }
+
+            // The ninth data bit is only meaningful with 9 data bits
+            impl serial::Read<u16> for Rx<$UARTX> {
+                type Error = Error;
+
+                /// Reads a word
+                ///
+                /// A word received with noise, or followed by an overrun, is still returned, and
+                /// the noise or overrun error is reported by the next read instead
+                fn read(&mut self) -> nb::Result<u16, Error> {
+                    if let Some(error) = self.error.take() {
+                        return Err(nb::Error::Other(error));
+                    }
+
+                    match self.read_word() {
+                        Ok(word) => {
+                            self.error = ErrorFlags {
+                                noise: word.noise,
+                                overrun: word.overrun,
+                                ..ErrorFlags::default()
+                            }.error();
+                            Ok(word.data)
+                        },
+                        // An error is always raised along with the flags
+                        Err(error) => Err(error.map(|flags| flags.error().unwrap())),
+                    }
+                }
+            }
+
+            impl serial::Write<u8> for Tx<$UARTX> {
+                // Besides FIFO overflows, the only possible errors during transmission are framing
+                // errors, which only occur in SmartCard mode. A deasserted clear to send is not an
+                // error, the word is held in the transmit buffer which reports `WouldBlock`
+                type Error = Error;
+
+                fn flush(&mut self) -> nb::Result<(), Error> {
+                    if $uartX().s1.read().tc().bit_is_clear() {
+                        return Err(nb::Error::WouldBlock);
+                    }
+
+                    Ok(())
+                }
+
+                fn write(&mut self, byte: u8) -> nb::Result<(), Error> {
+                    self.ready()?;
+
+                    $uartX().d.write(|w| unsafe { w.bits(byte) });
+
+                    Ok(())
+                }
+            }
+
+            impl Tx<$UARTX> {
+                /// Sends an address word, with the ninth bit set, to wake up the receivers of a
+                /// multi-drop bus in 9-bit mode
+                pub fn write_address(&mut self, address: u8) -> nb::Result<(), Error> {
+                    self.ready()?;
+
+                    let uart = $uartX();
+
+                    // The ninth bit is latched when the data register is written, it is restored
+                    // afterwards so the following bytes are sent as data
+                    // Reference: 47.3.7 UART Control Register 3 (UART_C3)
+                    interrupt::free(|_| {
+                        let t8 = uart.c3.read().t8().bit();
+                        uart.c3.modify(|_, w| w.t8().set_bit());
+                        uart.d.write(|w| unsafe { w.bits(address) });
+                        uart.c3.modify(|_, w| w.t8().bit(t8));
+                    });
+
+                    Ok(())
+                }
+            }
+
+            // The ninth data bit is only meaningful with 9 data bits, with 2 stop bits it must be
+            // kept high
+            impl serial::Write<u16> for Tx<$UARTX> {
+                type Error = Error;
+
+                fn flush(&mut self) -> nb::Result<(), Error> {
+                    serial::Write::<u8>::flush(self)
+                }
+
+                fn write(&mut self, word: u16) -> nb::Result<(), Error> {
+                    self.ready()?;
+
+                    let uart = $uartX();
+
+                    // The ninth bit must be written before the data register
+                    // Reference: 47.3.7 UART Control Register 3 (UART_C3)
+                    interrupt::free(|_| uart.c3.modify(|_, w| w.t8().bit(word.get_bit(8))));
+                    uart.d.write(|w| unsafe { w.bits(word as u8) });
+
+                    Ok(())
+                }
+            }
+        )+
+    }
+}
+
+// UART0 and UART1 are clocked by the core clock, the other UARTs by the bus clock
+// Reference: 5.7 Module clocks
+hal! {
+    UART0: (uart0, uart0_half_duplex, core),
+    UART1: (uart1, uart1_half_duplex, core),
+    UART2: (uart2, uart2_half_duplex, bus),
+    UART3: (uart3, uart3_half_duplex, bus),
+    UART4: (uart4, uart4_half_duplex, bus),
+}
+
+macro_rules! fifo {
+    ($(
+        $UARTX:ident: ($uartX:ident),
+    )+) => {
+        $(
+            impl<PINS> Serial<$UARTX, PINS> {
+                /// Enables the TX and RX FIFOs, discarding any buffered data
+                ///
+                /// The transmitter requests data once the TX FIFO holds `tx_watermark` words or
+                /// fewer, and the receiver signals data once the RX FIFO holds `rx_watermark` words
+                /// or more
+                pub fn enable_fifo(&mut self, tx_watermark: u8, rx_watermark: u8) {
+                    let pfifo = self.uart.pfifo.read();
+
+                    if tx_watermark >= fifo_size(pfifo.txfifosize().bits()) {
+                        panic!("Invalid UART TX FIFO watermark: {}", tx_watermark);
+                    }
+
+                    if rx_watermark == 0 || rx_watermark > fifo_size(pfifo.rxfifosize().bits()) {
+                        panic!("Invalid UART RX FIFO watermark: {}", rx_watermark);
+                    }
+
+                    self.set_fifo(true);
+
+                    // Reference: 47.3.19 UART FIFO Transmit Watermark (UART_TWFIFO)
+                    self.uart.twfifo.write(|w| unsafe { w.txwater().bits(tx_watermark) });
+
+                    // Reference: 47.3.21 UART FIFO Receive Watermark (UART_RWFIFO)
+                    self.uart.rwfifo.write(|w| unsafe { w.rxwater().bits(rx_watermark) });
+                }
+
+                /// Disables the TX and RX FIFOs, discarding any buffered data
+                pub fn disable_fifo(&mut self) {
+                    self.set_fifo(false);
+                    self.uart.twfifo.write(|w| unsafe { w.txwater().bits(0) });
+                    self.uart.rwfifo.write(|w| unsafe { w.rxwater().bits(1) });
+                }
+
+                /// Returns the number of words waiting in the TX FIFO
+                pub fn tx_fifo_count(&self) -> u8 {
+                    self.uart.tcfifo.read().txcount().bits()
+                }
+
+                /// Returns the number of words waiting in the RX FIFO
+                pub fn rx_fifo_count(&self) -> u8 {
+                    self.uart.rcfifo.read().rxcount().bits()
+                }
+
+                // The FIFOs can only be enabled or disabled while the transmitter and the receiver
+                // are disabled, and must be flushed afterwards
+                // Reference: 47.3.16 UART FIFO Parameters (UART_PFIFO)
+                fn set_fifo(&mut self, enable: bool) {
+                    let c2 = self.uart.c2.read();
+                    let (transmitter, receiver) = (c2.te().bit_is_set(), c2.re().bit_is_set());
+
+                    self.uart.c2.modify(|_, w| w.te().clear_bit().re().clear_bit());
+                    self.uart.pfifo.modify(|_, w| w.txfe().bit(enable).rxfe().bit(enable));
+
+                    // Reference: 47.3.17 UART FIFO Control Register (UART_CFIFO)
+                    self.uart.cfifo.modify(|_, w| w.txflush().set_bit().rxflush().set_bit());
+
+                    self.uart.c2.modify(|_, w| w.te().bit(transmitter).re().bit(receiver));
+                }
+            }
+
+            impl Tx<$UARTX> {
+                /// Returns the number of words waiting in the TX FIFO
+                pub fn fifo_count(&self) -> u8 {
+                    $uartX().tcfifo.read().txcount().bits()
+                }
+            }
+
+            impl Rx<$UARTX> {
+                /// Returns the number of words waiting in the RX FIFO
+                pub fn fifo_count(&self) -> u8 {
+                    $uartX().rcfifo.read().rxcount().bits()
+                }
+            }
+        )+
+    }
+}
+
+// Only UART0 and UART1 have 8 word FIFOs, the FIFOs of the other UARTs hold a single word
+// Reference: 47.1.1 Features
+fifo! {
+    UART0: (uart0),
+    UART1: (uart1),
+}
+
+#[cfg(test)]
+mod tests {
+    use bitrate::{Bps, Hertz};
+
+    use super::{baud_rate_divisors, fifo_size, Error, ErrorFlags};
+
+    #[test]
+    fn exact_divisors() {
+        // 72 MHz core clock
+        assert_eq!(baud_rate_divisors(Hertz(72_000_000), Bps(115_200)).unwrap(), (39, 2));
+
+        // 36 MHz bus clock
+        assert_eq!(baud_rate_divisors(Hertz(36_000_000), Bps(115_200)).unwrap(), (19, 17));
+
+        // 48 MHz core clock
+        assert_eq!(baud_rate_divisors(Hertz(48_000_000), Bps(9_600)).unwrap(), (312, 16));
+    }
+
+    #[test]
+    fn rounds_to_the_closest_divisor() {
+        // 2 * 72 MHz / 1 Mbps = 144 exactly, 2 * 72 MHz / 921.6 kbps = 156.25
+        assert_eq!(baud_rate_divisors(Hertz(72_000_000), Bps(1_000_000)).unwrap(), (4, 16));
+        assert_eq!(baud_rate_divisors(Hertz(72_000_000), Bps(921_600)).unwrap(), (4, 28));
+
+        // 2 * 16 MHz / 115.2 kbps = 277.78, rounded up
+        assert_eq!(baud_rate_divisors(Hertz(16_000_000), Bps(115_200)).unwrap(), (8, 22));
+    }
+
+    #[test]
+    fn baud_rate_too_high() {
+        let error = baud_rate_divisors(Hertz(1_000_000), Bps(115_200)).unwrap_err();
+        assert_eq!(error.baud_rate.0, 62_500);
+        assert!(error.error < -45.0 && error.error > -46.0);
+    }
+
+    #[test]
+    fn baud_rate_too_low() {
+        let error = baud_rate_divisors(Hertz(72_000_000), Bps(100)).unwrap_err();
+        assert_eq!(error.baud_rate.0, 549);
+        assert!(error.error > 400.0);
+    }
+
+    #[test]
+    fn fifo_sizes() {
+        assert_eq!(fifo_size(0b000), 1);
+        assert_eq!(fifo_size(0b001), 4);
+        assert_eq!(fifo_size(0b010), 8);
+        assert_eq!(fifo_size(0b110), 128);
+
+        // Reserved
+        assert_eq!(fifo_size(0b111), 1);
+    }
+
+    #[test]
+    fn divisor_limits_are_accepted() {
+        // SBR = 1, the highest baud rate of a 72 MHz clock
+        assert_eq!(baud_rate_divisors(Hertz(72_000_000), Bps(4_500_000)).unwrap(), (1, 0));
+
+        // SBR = 8191 with the largest fine adjustment
+        assert_eq!(baud_rate_divisors(Hertz(72_000_000), Bps(549)).unwrap(), (8191, 31));
+    }
+
+    #[test]
+    fn error_flags_report_the_most_severe_error() {
+        assert!(ErrorFlags::default().error().is_none());
+
+        let flags = ErrorFlags { noise: true, overrun: true, ..ErrorFlags::default() };
+        assert!(matches!(flags.error(), Some(Error::Noise)));
+
+        let flags = ErrorFlags { framing: true, parity: true, ..flags };
+        assert!(matches!(flags.error(), Some(Error::Parity)));
+    }
+
+    #[test]
+    fn error_flags_report_overruns() {
+        let flags = ErrorFlags { overrun: true, ..ErrorFlags::default() };
+        assert!(matches!(flags.error(), Some(Error::Overrun)));
+
+        let flags = ErrorFlags { fifo_underflow: true, ..flags };
+        assert!(matches!(flags.error(), Some(Error::Overrun)));
+    }
+}
@@ -0,0 +1,160 @@
+use core::marker::PhantomData;
+
+use cortex_m::interrupt;
+use nb;
+use hal::serial;
+use mk20d7::{UART0, UART1, UART2, UART3, UART4};
+
+use super::{BufferedSerial, Error, RtsPin, Rx, Serial, Tx};
+
+/// RS-485 transceiver configuration
+#[derive(Clone, Copy, Debug)]
+pub struct Rs485 {
+    /// The driver enable is asserted with a high level
+    pub driver_enable_active_high: bool,
+
+    /// Disables the receiver while transmitting, so the frames sent are not received back
+    pub disable_receiver_while_transmitting: bool,
+}
+
+impl Default for Rs485 {
+    /// Active high driver enable, the receiver is kept enabled
+    fn default() -> Self {
+        Rs485 {
+            driver_enable_active_high: true,
+            disable_receiver_while_transmitting: false,
+        }
+    }
+}
+
+/// RS-485 serial, the RTS pin drives the transceiver driver enable
+///
+/// RTS is asserted one bit time before the start bit, and deasserted one bit time after the last
+/// stop bit once the transmit buffer and FIFO are empty
+pub struct Rs485Serial<UART, PINS, RTS> {
+    serial: Serial<UART, PINS>,
+    rx: Rx<UART>,
+    rts: RTS,
+    config: Rs485,
+}
+
+macro_rules! rs485 {
+    ($(
+        $UARTX:ident,
+    )+) => {
+        $(
+            impl<TX, RX> Serial<$UARTX, (TX, RX)> {
+                /// Drives a RS-485 transceiver, asserting RTS while transmitting
+                pub fn into_rs485<RTS>(self, rts: RTS, config: Rs485) -> Rs485Serial<$UARTX, (TX, RX), RTS>
+                where
+                    RTS: RtsPin<$UARTX>,
+                {
+                    // Reference: 47.3.14 UART Modem Register (UART_MODEM)
+                    self.uart.modem.modify(|_, w| {
+                        w.txrtspol().bit(config.driver_enable_active_high);
+                        w.txrtse().set_bit()
+                    });
+
+                    let rx = Rx { _uart: PhantomData, error: None };
+                    Rs485Serial { serial: self, rx, rts, config }
+                }
+            }
+
+            impl<TX, RX, RTS> Rs485Serial<$UARTX, (TX, RX), RTS> {
+                /// Stops driving the RS-485 transceiver, and releases the serial and the RTS pin
+                pub fn into_serial(self) -> (Serial<$UARTX, (TX, RX)>, RTS) {
+                    while self.serial.uart.s1.read().tc().bit_is_clear() {}
+
+                    // Reference: 47.3.14 UART Modem Register (UART_MODEM)
+                    self.serial.uart.modem.modify(|_, w| {
+                        w.txrtspol().clear_bit();
+                        w.txrtse().clear_bit()
+                    });
+                    self.enable_receiver();
+
+                    (self.serial, self.rts)
+                }
+
+                /// Takes over the buffers to send and receive through interrupts, the receiver is
+                /// kept enabled while transmitting
+                ///
+                /// `BufferedSerial::free` releases the serial along with the RTS pin, still
+                /// driving the transceiver
+                pub fn into_buffered(
+                    self,
+                    tx_buffer: &'static mut [u8],
+                    rx_buffer: &'static mut [u8],
+                ) -> BufferedSerial<$UARTX, ((TX, RX), RTS)> {
+                    self.enable_receiver();
+
+                    let serial = Serial { uart: self.serial.uart, pins: (self.serial.pins, self.rts) };
+                    BufferedSerial::<$UARTX, _>::new(serial, tx_buffer, rx_buffer)
+                }
+
+                // The receiver shares the control register with the transmitter, so it is modified
+                // atomically
+                // Reference: 47.3.4 UART Control Register 2 (UART_C2)
+                fn enable_receiver(&self) {
+                    let uart = &self.serial.uart;
+                    interrupt::free(|_| uart.c2.modify(|_, w| w.re().set_bit()));
+                }
+
+                fn disable_receiver(&self) {
+                    let uart = &self.serial.uart;
+                    interrupt::free(|_| uart.c2.modify(|_, w| w.re().clear_bit()));
+                }
+            }
+
+            impl<TX, RX, RTS> serial::Read<u8> for Rs485Serial<$UARTX, (TX, RX), RTS> {
+                type Error = Error;
+
+                /// Enables the receiver again once the transmission is complete, if it was
+                /// disabled while transmitting
+                fn read(&mut self) -> nb::Result<u8, Error> {
+                    if self.config.disable_receiver_while_transmitting {
+                        let uart = &self.serial.uart;
+                        if uart.c2.read().re().bit_is_clear() {
+                            if uart.s1.read().tc().bit_is_clear() {
+                                return Err(nb::Error::WouldBlock);
+                            }
+                            self.enable_receiver();
+                        }
+                    }
+
+                    serial::Read::<u8>::read(&mut self.rx)
+                }
+            }
+
+            impl<TX, RX, RTS> serial::Write<u8> for Rs485Serial<$UARTX, (TX, RX), RTS> {
+                type Error = Error;
+
+                /// Waits for the transmission to complete, then enables the receiver again if it
+                /// was disabled while transmitting
+                fn flush(&mut self) -> nb::Result<(), Error> {
+                    serial::Write::<u8>::flush(&mut Tx::<$UARTX> { _uart: PhantomData })?;
+
+                    if self.config.disable_receiver_while_transmitting {
+                        self.enable_receiver();
+                    }
+                    Ok(())
+                }
+
+                fn write(&mut self, byte: u8) -> nb::Result<(), Error> {
+                    if self.config.disable_receiver_while_transmitting {
+                        self.disable_receiver();
+                    }
+
+                    serial::Write::<u8>::write(&mut Tx::<$UARTX> { _uart: PhantomData }, byte)
+                }
+            }
+        )+
+    }
+}
+
+rs485! {
+    UART0,
+    UART1,
+    UART2,
+    UART3,
+    UART4,
+}
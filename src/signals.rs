@@ -0,0 +1,542 @@
+use gpio::{
+    Alternate, ALT0, ALT2, ALT3, ALT4, ALT5, ALT6, ALT7,
+    gpioa::*, gpiob::*, gpioc::*, gpiod::*, gpioe::*,
+};
+use mk20d7::{
+    CAN0, CMP0, CMP1, CMP2, CMT, EWM, FTM0, FTM1, FTM2, I2C0, I2C1, I2S0, LPTMR0, SPI0, SPI1, TSI0,
+    USB0,
+};
+
+use self::{clkout::*, cmt::*, ftm::*, i2c::*, i2s::*, lptmr::*, spi::*, usb::*};
+
+/// Serial Peripheral Interface signals
+pub mod spi {
+    use sealed::Sealed;
+
+    /// Serial clock pin
+    pub trait SckPin<SPI>: Sealed {}
+
+    /// Serial data out pin
+    pub trait SoutPin<SPI>: Sealed {}
+
+    /// Serial data in pin
+    pub trait SinPin<SPI>: Sealed {}
+
+    /// Peripheral chip select pin
+    pub trait PcsPin<SPI, PCS>: Sealed {}
+
+    /// Peripheral chip select 0
+    pub struct Pcs0;
+
+    /// Peripheral chip select 1
+    pub struct Pcs1;
+
+    /// Peripheral chip select 2
+    pub struct Pcs2;
+
+    /// Peripheral chip select 3
+    pub struct Pcs3;
+
+    /// Peripheral chip select 4
+    pub struct Pcs4;
+
+    /// Peripheral chip select 5
+    pub struct Pcs5;
+}
+
+/// Inter-Integrated Circuit signals
+pub mod i2c {
+    use sealed::Sealed;
+
+    /// Serial clock pin
+    pub trait SclPin<I2C>: Sealed {}
+
+    /// Serial data pin
+    pub trait SdaPin<I2C>: Sealed {}
+}
+
+/// FlexTimer Module signals
+pub mod ftm {
+    use sealed::Sealed;
+
+    /// Channel pin
+    pub trait ChannelPin<FTM, CHANNEL>: Sealed {}
+
+    /// Quadrature decoder phase A pin
+    pub trait QdPhAPin<FTM>: Sealed {}
+
+    /// Quadrature decoder phase B pin
+    pub trait QdPhBPin<FTM>: Sealed {}
+
+    /// Fault input pin
+    pub trait FaultPin<FTM, FAULT>: Sealed {}
+
+    /// External clock input pin, shared by every FlexTimer Module
+    pub trait ClockInPin<CLKIN>: Sealed {}
+
+    /// Channel 0
+    pub struct C0;
+
+    /// Channel 1
+    pub struct C1;
+
+    /// Channel 2
+    pub struct C2;
+
+    /// Channel 3
+    pub struct C3;
+
+    /// Channel 4
+    pub struct C4;
+
+    /// Channel 5
+    pub struct C5;
+
+    /// Channel 6
+    pub struct C6;
+
+    /// Channel 7
+    pub struct C7;
+
+    /// Fault input 0
+    pub struct Flt0;
+
+    /// Fault input 1
+    pub struct Flt1;
+
+    /// Fault input 2
+    pub struct Flt2;
+
+    /// Fault input 3
+    pub struct Flt3;
+
+    /// External clock input 0
+    pub struct ClkIn0;
+
+    /// External clock input 1
+    pub struct ClkIn1;
+}
+
+/// FlexCAN signals
+pub mod can {
+    use sealed::Sealed;
+
+    /// Transmit pin
+    pub trait TxPin<CAN>: Sealed {}
+
+    /// Receive pin
+    pub trait RxPin<CAN>: Sealed {}
+}
+
+/// Integrated Interchip Sound signals
+pub mod i2s {
+    use sealed::Sealed;
+
+    /// Transmit bit clock pin
+    pub trait TxBclkPin<I2S>: Sealed {}
+
+    /// Transmit frame sync pin
+    pub trait TxFsPin<I2S>: Sealed {}
+
+    /// Transmit data pin
+    pub trait TxdPin<I2S, DATA>: Sealed {}
+
+    /// Receive bit clock pin
+    pub trait RxBclkPin<I2S>: Sealed {}
+
+    /// Receive frame sync pin
+    pub trait RxFsPin<I2S>: Sealed {}
+
+    /// Receive data pin
+    pub trait RxdPin<I2S, DATA>: Sealed {}
+
+    /// Master clock pin
+    pub trait MclkPin<I2S>: Sealed {}
+
+    /// Data line 0
+    pub struct D0;
+
+    /// Data line 1
+    pub struct D1;
+}
+
+/// Comparator signals
+pub mod cmp {
+    use sealed::Sealed;
+
+    /// Analog input pin
+    pub trait InPin<CMP, INPUT>: Sealed {}
+
+    /// Output pin
+    pub trait OutPin<CMP>: Sealed {}
+
+    /// Analog input 0
+    pub struct In0;
+
+    /// Analog input 1
+    pub struct In1;
+
+    /// Analog input 2
+    pub struct In2;
+
+    /// Analog input 3
+    pub struct In3;
+}
+
+/// Touch Sensing Input signals
+pub mod tsi {
+    use sealed::Sealed;
+
+    /// Electrode pin
+    pub trait ChannelPin<TSI, CHANNEL>: Sealed {}
+
+    /// Channel 0
+    pub struct C0;
+
+    /// Channel 1
+    pub struct C1;
+
+    /// Channel 2
+    pub struct C2;
+
+    /// Channel 3
+    pub struct C3;
+
+    /// Channel 4
+    pub struct C4;
+
+    /// Channel 5
+    pub struct C5;
+
+    /// Channel 6
+    pub struct C6;
+
+    /// Channel 7
+    pub struct C7;
+
+    /// Channel 8
+    pub struct C8;
+
+    /// Channel 9
+    pub struct C9;
+
+    /// Channel 10
+    pub struct C10;
+
+    /// Channel 11
+    pub struct C11;
+
+    /// Channel 12
+    pub struct C12;
+
+    /// Channel 13
+    pub struct C13;
+
+    /// Channel 14
+    pub struct C14;
+
+    /// Channel 15
+    pub struct C15;
+}
+
+/// Carrier Modulator Transmitter signals
+pub mod cmt {
+    use sealed::Sealed;
+
+    /// Infrared output pin
+    pub trait IroPin<CMT>: Sealed {}
+}
+
+/// External Watchdog Monitor signals
+pub mod ewm {
+    use sealed::Sealed;
+
+    /// Input pin
+    pub trait InPin<EWM>: Sealed {}
+
+    /// Output pin (active low)
+    pub trait OutPin<EWM>: Sealed {}
+}
+
+/// Universal Serial Bus signals
+pub mod usb {
+    use sealed::Sealed;
+
+    /// Clock input pin
+    pub trait ClkInPin<USB>: Sealed {}
+
+    /// Start of frame output pin
+    pub trait SofOutPin<USB>: Sealed {}
+}
+
+/// Low-Power Timer signals
+pub mod lptmr {
+    use sealed::Sealed;
+
+    /// Pulse counter input pin
+    pub trait AltPin<LPTMR, ALT>: Sealed {}
+
+    /// Pulse counter input 1
+    pub struct Alt1;
+
+    /// Pulse counter input 2
+    pub struct Alt2;
+}
+
+/// Clock output signals
+pub mod clkout {
+    use sealed::Sealed;
+
+    /// Clock output pin
+    pub trait ClkOutPin: Sealed {}
+}
+
+macro_rules! signals {
+    ($( $Signal:path: [ $($(#[$attr:meta])* $PTXi:ident<$ALT:ident>,)+ ], )+) => {
+        $(
+            $(
+                $(#[$attr])*
+                impl $Signal for $PTXi<Alternate<$ALT>> {}
+            )+
+        )+
+    }
+}
+
+// Reference: 10.3.1 K20 Signal Multiplexing and Pin Assignments
+signals! {
+    // Serial Peripheral Interface 0
+    SckPin<SPI0>: [
+        PTC5<ALT2>,
+        PTD1<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTA15<ALT2>,
+    ],
+    SoutPin<SPI0>: [
+        PTC6<ALT2>,
+        PTD2<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTA16<ALT2>,
+    ],
+    SinPin<SPI0>: [
+        PTC7<ALT2>,
+        PTD3<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTA17<ALT2>,
+    ],
+    PcsPin<SPI0, Pcs0>: [
+        PTC4<ALT2>,
+        PTD0<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTA14<ALT2>,
+    ],
+    PcsPin<SPI0, Pcs1>: [PTC3<ALT2>, PTD4<ALT2>,],
+    PcsPin<SPI0, Pcs2>: [PTC2<ALT2>, PTD5<ALT2>,],
+    PcsPin<SPI0, Pcs3>: [PTC1<ALT2>, PTD6<ALT2>,],
+    PcsPin<SPI0, Pcs4>: [PTC0<ALT2>,],
+    PcsPin<SPI0, Pcs5>: [
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTB23<ALT3>,
+    ],
+
+    // Serial Peripheral Interface 1
+    SckPin<SPI1>: [
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTB11<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTE2<ALT2>,
+    ],
+    SoutPin<SPI1>: [
+        PTB16<ALT2>,
+        PTE1<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTE3<ALT7>,
+    ],
+    SinPin<SPI1>: [
+        PTB17<ALT2>,
+        PTE1<ALT7>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTE3<ALT2>,
+    ],
+    PcsPin<SPI1, Pcs0>: [
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTB10<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTE4<ALT2>,
+    ],
+    PcsPin<SPI1, Pcs1>: [
+        PTE0<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTB9<ALT2>,
+    ],
+    PcsPin<SPI1, Pcs2>: [
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTE5<ALT2>,
+    ],
+    PcsPin<SPI1, Pcs3>: [
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTE6<ALT2>,
+    ],
+
+    // Inter-Integrated Circuit 0
+    SclPin<I2C0>: [
+        PTB0<ALT2>,
+        PTB2<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTD8<ALT2>,
+    ],
+    SdaPin<I2C0>: [
+        PTB1<ALT2>,
+        PTB3<ALT2>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTD9<ALT2>,
+    ],
+
+    // Inter-Integrated Circuit 1
+    SclPin<I2C1>: [PTC10<ALT2>, PTE1<ALT6>,],
+    SdaPin<I2C1>: [PTC11<ALT2>, PTE0<ALT6>,],
+
+    // FlexTimer Module 0
+    ChannelPin<FTM0, C0>: [PTA3<ALT3>, PTC1<ALT4>,],
+    ChannelPin<FTM0, C1>: [PTA4<ALT3>, PTC2<ALT4>,],
+    ChannelPin<FTM0, C2>: [PTA5<ALT3>, PTC3<ALT4>,],
+    ChannelPin<FTM0, C3>: [PTC4<ALT4>,],
+    ChannelPin<FTM0, C4>: [PTD4<ALT4>,],
+    ChannelPin<FTM0, C5>: [PTA0<ALT3>, PTD5<ALT4>,],
+    ChannelPin<FTM0, C6>: [PTA1<ALT3>, PTD6<ALT4>,],
+    ChannelPin<FTM0, C7>: [PTA2<ALT3>, PTD7<ALT4>,],
+    FaultPin<FTM0, Flt0>: [PTB3<ALT6>, PTD6<ALT6>,],
+    FaultPin<FTM0, Flt1>: [
+        PTD7<ALT6>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTB10<ALT6>,
+    ],
+    FaultPin<FTM0, Flt2>: [
+        PTA18<ALT3>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTB11<ALT6>,
+    ],
+    FaultPin<FTM0, Flt3>: [PTB2<ALT6>,],
+
+    // FlexTimer Module 1
+    ChannelPin<FTM1, C0>: [PTA12<ALT3>, PTB0<ALT3>,],
+    ChannelPin<FTM1, C1>: [PTA13<ALT3>, PTB1<ALT3>,],
+    QdPhAPin<FTM1>: [PTA12<ALT7>, PTB0<ALT6>,],
+    QdPhBPin<FTM1>: [PTA13<ALT7>, PTB1<ALT6>,],
+    FaultPin<FTM1, Flt0>: [PTA19<ALT3>,],
+
+    // FlexTimer Module 2
+    ChannelPin<FTM2, C0>: [PTB18<ALT3>,],
+    ChannelPin<FTM2, C1>: [PTB19<ALT3>,],
+    QdPhAPin<FTM2>: [PTB18<ALT6>,],
+    QdPhBPin<FTM2>: [PTB19<ALT6>,],
+    FaultPin<FTM2, Flt0>: [PTC9<ALT6>,],
+
+    // FlexTimer Module external clocks
+    ClockInPin<ClkIn0>: [PTA18<ALT4>,],
+    ClockInPin<ClkIn1>: [PTA19<ALT4>,],
+
+    // FlexCAN 0
+    can::TxPin<CAN0>: [PTA12<ALT2>, PTB18<ALT2>,],
+    can::RxPin<CAN0>: [PTA13<ALT2>, PTB19<ALT2>,],
+
+    // Integrated Interchip Sound 0
+    TxBclkPin<I2S0>: [PTA5<ALT6>, PTB18<ALT4>, PTC3<ALT6>,],
+    TxFsPin<I2S0>: [PTA13<ALT6>, PTB19<ALT4>, PTC2<ALT6>,],
+    TxdPin<I2S0, D0>: [PTA12<ALT6>, PTC1<ALT6>,],
+    TxdPin<I2S0, D1>: [PTC0<ALT6>,],
+    RxBclkPin<I2S0>: [
+        PTC6<ALT4>,
+        PTC9<ALT4>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTA14<ALT6>,
+    ],
+    RxFsPin<I2S0>: [
+        PTC7<ALT4>,
+        PTC10<ALT4>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTA16<ALT6>,
+    ],
+    RxdPin<I2S0, D0>: [
+        PTC5<ALT4>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTA15<ALT6>,
+    ],
+    RxdPin<I2S0, D1>: [
+        PTC11<ALT4>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTA16<ALT7>,
+    ],
+    MclkPin<I2S0>: [
+        PTC6<ALT6>,
+        PTC8<ALT4>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTA17<ALT6>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTE6<ALT4>,
+    ],
+
+    // Comparators
+    cmp::InPin<CMP0, cmp::In0>: [PTC6<ALT0>,],
+    cmp::InPin<CMP0, cmp::In1>: [PTC7<ALT0>,],
+    cmp::InPin<CMP0, cmp::In2>: [PTC8<ALT0>,],
+    cmp::InPin<CMP0, cmp::In3>: [PTC9<ALT0>,],
+    cmp::InPin<CMP1, cmp::In0>: [PTC2<ALT0>,],
+    cmp::InPin<CMP1, cmp::In1>: [PTC3<ALT0>,],
+    cmp::InPin<CMP2, cmp::In0>: [PTA12<ALT0>,],
+    cmp::InPin<CMP2, cmp::In1>: [PTA13<ALT0>,],
+    cmp::OutPin<CMP0>: [
+        PTC5<ALT6>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTB20<ALT6>,
+    ],
+    cmp::OutPin<CMP1>: [
+        PTC4<ALT6>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTB21<ALT6>,
+    ],
+    cmp::OutPin<CMP2>: [
+        PTA5<ALT5>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTB22<ALT6>,
+    ],
+
+    // Touch Sensing Input
+    tsi::ChannelPin<TSI0, tsi::C0>: [PTB0<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C1>: [PTA0<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C2>: [PTA1<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C3>: [PTA2<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C4>: [PTA3<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C5>: [PTA4<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C6>: [PTB1<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C7>: [PTB2<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C8>: [PTB3<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C9>: [PTB16<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C10>: [PTB17<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C11>: [PTB18<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C12>: [PTB19<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C13>: [PTC0<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C14>: [PTC1<ALT0>,],
+    tsi::ChannelPin<TSI0, tsi::C15>: [PTC2<ALT0>,],
+
+    // Carrier Modulator Transmitter
+    IroPin<CMT>: [PTD7<ALT2>,],
+
+    // External Watchdog Monitor
+    ewm::InPin<EWM>: [PTB16<ALT7>, PTD4<ALT6>,],
+    ewm::OutPin<EWM>: [PTB17<ALT7>, PTD5<ALT6>,],
+
+    // Universal Serial Bus
+    ClkInPin<USB0>: [PTA5<ALT2>,],
+    SofOutPin<USB0>: [
+        PTC7<ALT3>,
+        #[cfg(any(feature = "lqfp100", feature = "mapbga121"))]
+        PTE6<ALT6>,
+    ],
+
+    // Low-Power Timer
+    AltPin<LPTMR0, Alt1>: [PTA19<ALT6>,],
+    AltPin<LPTMR0, Alt2>: [PTC5<ALT3>,],
+
+    // Clock output
+    ClkOutPin: [PTC3<ALT5>,],
+}
@@ -1,7 +1,35 @@
+use bitrate::{U32BitrateExt, Hertz};
 use mk20d7::{sim::RegisterBlock, sim::clkdiv1};
 
+use mcg::MultipurposeClockGenerator;
+
 pub const MAXIMUM_CLOCK_FREQUENCY: u8 = 72;
 
+/// Clock frequencies frozen from the MCG output and the SIM dividers
+#[derive(Clone, Copy, Debug)]
+pub struct Clocks {
+    core: Hertz<u32>,
+    bus: Hertz<u32>,
+    flash: Hertz<u32>,
+}
+
+impl Clocks {
+    /// Core and system clock frequency, which also clocks UART0 and UART1
+    pub fn core(&self) -> Hertz<u32> {
+        self.core
+    }
+
+    /// Bus clock frequency, which clocks the remaining peripherals
+    pub fn bus(&self) -> Hertz<u32> {
+        self.bus
+    }
+
+    /// Flash clock frequency
+    pub fn flash(&self) -> Hertz<u32> {
+        self.flash
+    }
+}
+
 pub struct SystemIntegrationModule<'a> {
     sim: &'a RegisterBlock,
 }
@@ -151,6 +179,17 @@ impl<'a> SystemIntegrationModule<'a> {
         (core, bus, flash)
     }
 
+    // Reference: 12.2.16 System Clock Divider Register 1 (SIM_CLKDIV1)
+    pub fn freeze(&self, mcg: &MultipurposeClockGenerator) -> Clocks {
+        let output = mcg.get_output_frequency().0;
+        let (core, bus, flash) = self.get_dividers();
+        Clocks {
+            core: (output / u32::from(core)).hz(),
+            bus: (output / u32::from(bus)).hz(),
+            flash: (output / u32::from(flash)).hz(),
+        }
+    }
+
     pub fn get_frequencies(&self) -> (u8, u8, u8) {
         let (core, bus, flash) = self.get_dividers();
         (